@@ -1,14 +1,19 @@
-use std::fmt::{Debug, Formatter, Result};
+use std::fmt::{Debug, Display, Formatter, Result};
+use std::str::FromStr;
 
 use crate::board::Cell::{Fixed, Free};
 use bitmask::bitmask;
 
+/// Size of the classic board, the one used by default.
 pub const BOARD_BOX_SIZE: usize = 3;
 pub const BOARD_SIZE: usize = BOARD_BOX_SIZE * BOARD_BOX_SIZE;
 
+/// Size of the largest board, whose values go from 1 to 9 and then from A to P.
+pub const MAX_BOARD_SIZE: usize = 25;
+
 bitmask! {
 
-    mask FreeNumberMask: u16 where flags FreeNumberFlags {
+    pub mask FreeNumberMask: u32 where flags FreeNumberFlags {
         _0 = 0, // unused to easy index
         _1 = 1 << 0,
         _2 = 1 << 1,
@@ -18,20 +23,53 @@ bitmask! {
         _6 = 1 << 5,
         _7 = 1 << 6,
         _8 = 1 << 7,
-        _9 = 1 << 8
+        _9 = 1 << 8,
+        _10 = 1 << 9,
+        _11 = 1 << 10,
+        _12 = 1 << 11,
+        _13 = 1 << 12,
+        _14 = 1 << 13,
+        _15 = 1 << 14,
+        _16 = 1 << 15,
+        _17 = 1 << 16,
+        _18 = 1 << 17,
+        _19 = 1 << 18,
+        _20 = 1 << 19,
+        _21 = 1 << 20,
+        _22 = 1 << 21,
+        _23 = 1 << 22,
+        _24 = 1 << 23,
+        _25 = 1 << 24
     }
 
 }
 
-impl Default for FreeNumberMask {
-    fn default() -> Self {
-        FreeNumberMask::all()
+impl FreeNumberMask {
+    pub fn from_value(val: u8) -> FreeNumberMask {
+        FreeNumberFlags::from(val as u16).into()
+    }
+
+    /// The values from 1 to `size`, all the values of a board of this size.
+    pub fn up_to(size: usize) -> FreeNumberMask {
+        (1..=size as u8).fold(FreeNumberMask::none(), |mask, val| {
+            mask | FreeNumberMask::from_value(val)
+        })
+    }
+
+    pub fn contains_value(&self, val: u8) -> bool {
+        self.contains(FreeNumberFlags::from(val as u16))
+    }
+
+    pub fn values(&self) -> Vec<u8> {
+        (1..=MAX_BOARD_SIZE as u8)
+            .filter(|d| self.contains_value(*d))
+            .collect()
     }
 }
 
 impl Debug for FreeNumberMask {
     fn fmt(&self, f: &mut Formatter<'_>) -> Result {
-        let v: Vec<u8> = (0u16..=9)
+        let v: Vec<u8> = (0u16..=MAX_BOARD_SIZE as u16)
             .into_iter()
             .map(|d| self.contains(Into::<FreeNumberFlags>::into(d)))
             .map(|b| if b { 1 } else { 0 })
@@ -42,7 +80,7 @@ impl Debug for FreeNumberMask {
 }
 
 impl FreeNumberFlags {
-    const VALUES: [Self; BOARD_SIZE + 1] = [
+    const VALUES: [Self; MAX_BOARD_SIZE + 1] = [
         FreeNumberFlags::_0,
         FreeNumberFlags::_1,
         FreeNumberFlags::_2,
@@ -53,6 +91,22 @@ impl FreeNumberFlags {
         FreeNumberFlags::_7,
         FreeNumberFlags::_8,
         FreeNumberFlags::_9,
+        FreeNumberFlags::_10,
+        FreeNumberFlags::_11,
+        FreeNumberFlags::_12,
+        FreeNumberFlags::_13,
+        FreeNumberFlags::_14,
+        FreeNumberFlags::_15,
+        FreeNumberFlags::_16,
+        FreeNumberFlags::_17,
+        FreeNumberFlags::_18,
+        FreeNumberFlags::_19,
+        FreeNumberFlags::_20,
+        FreeNumberFlags::_21,
+        FreeNumberFlags::_22,
+        FreeNumberFlags::_23,
+        FreeNumberFlags::_24,
+        FreeNumberFlags::_25,
     ];
 }
 
@@ -81,12 +135,29 @@ impl Into<u16> for FreeNumberFlags {
     }
 }
 
+/// The character showing a value: 1 to 9, then A to P on the boards larger than 9x9.
+pub fn value_to_char(val: u8) -> char {
+    match val {
+        1..=9 => (b'0' + val) as char,
+        _ => (b'A' + val - 10) as char,
+    }
+}
+
+/// The value shown by a character, ignoring case, or `None` if it doesn't show any value.
+pub fn char_to_value(c: char) -> Option<u8> {
+    match c.to_ascii_uppercase() {
+        c @ '1'..='9' => Some(c as u8 - b'0'),
+        c @ 'A'..='P' => Some(c as u8 - b'A' + 10),
+        _ => None,
+    }
+}
+
 #[derive(Default, Clone, Copy)]
 pub struct Value(u8);
 
 impl From<u8> for Value {
     fn from(val: u8) -> Self {
-        assert!(val <= 9);
+        assert!(val as usize <= MAX_BOARD_SIZE);
         Value(val)
     }
 }
@@ -97,15 +168,16 @@ impl Into<u8> for Value {
     }
 }
 
+/// A free cell keeps the notes of the player, the values they consider for it.
 #[derive(Clone, Copy)]
 pub enum Cell {
     Fixed(Value),
-    Free(Value),
+    Free(Value, FreeNumberMask),
 }
 
 impl Default for Cell {
     fn default() -> Self {
-        Free(0u8.into())
+        Free(0u8.into(), FreeNumberMask::none())
     }
 }
 
@@ -113,7 +185,7 @@ impl Into<u8> for Cell {
     fn into(self) -> u8 {
         match self {
             Fixed(v) => v,
-            Free(v) => v,
+            Free(v, _) => v,
         }
         .into()
     }
@@ -125,7 +197,7 @@ impl Cell {
         if fixed {
             Fixed(val)
         } else {
-            Free(val)
+            Free(val, FreeNumberMask::none())
         }
     }
 
@@ -134,12 +206,591 @@ impl Cell {
     }
 }
 
-#[derive(Default, Clone)]
+/// A group of cells that must contain each value exactly once.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Unit {
+    Row(usize),
+    Column(usize),
+    Box(usize),
+    /// The diagonal from the top left corner (0) or from the top right corner (1).
+    Diagonal(usize),
+}
+
+impl Display for Unit {
+    fn fmt(&self, f: &mut Formatter<'_>) -> Result {
+        match *self {
+            Unit::Row(row) => write!(f, "row {}", row + 1),
+            Unit::Column(col) => write!(f, "column {}", col + 1),
+            Unit::Box(b) => write!(f, "box {}", b + 1),
+            Unit::Diagonal(d) => write!(f, "diagonal {}", d + 1),
+        }
+    }
+}
+
+/// The size of a board and the units its cells are grouped in. A board whose boxes have R rows and
+/// C columns has R×C rows, columns and boxes, with C rows of R boxes. In jigsaw sudoku, a region
+/// map replaces the boxes with irregular regions of as many connected cells. In the diagonal
+/// variant (Sudoku X), both diagonals are units too.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Shape {
+    box_rows: usize,
+    box_cols: usize,
+    diagonals: bool,
+    /// The region of each cell, row by row, when the boxes are irregular.
+    regions: Option<Vec<usize>>,
+}
+
+impl Default for Shape {
+    fn default() -> Self {
+        Shape::new(BOARD_BOX_SIZE)
+    }
+}
+
+impl Shape {
+    /// A board with square boxes of `box_size` rows and columns.
+    pub fn new(box_size: usize) -> Shape {
+        Shape::with_boxes(box_size, box_size)
+    }
+
+    /// A board whose boxes have `box_rows` rows and `box_cols` columns, like the 2x3 boxes of a
+    /// 6x6 board.
+    pub fn with_boxes(box_rows: usize, box_cols: usize) -> Shape {
+        assert!(box_rows >= 2 && box_cols >= 2 && box_rows * box_cols <= MAX_BOARD_SIZE);
+        Shape {
+            box_rows,
+            box_cols,
+            diagonals: false,
+            regions: None,
+        }
+    }
+
+    /// The shape of the boards with `size` rows, if there is one. The boxes are as square as
+    /// possible, and wider than tall otherwise.
+    pub fn from_size(size: usize) -> Option<Shape> {
+        if size > MAX_BOARD_SIZE {
+            return None;
+        }
+        (2..size)
+            .take_while(|box_rows| box_rows * box_rows <= size)
+            .filter(|box_rows| size.is_multiple_of(*box_rows))
+            .last()
+            .map(|box_rows| Shape::with_boxes(box_rows, size / box_rows))
+    }
+
+    /// The shape of the board given in the one-line format, found from its number of cells.
+    pub fn from_one_line(s: &str) -> std::result::Result<Shape, String> {
+        let nb_cells = s
+            .chars()
+            .filter(|c| !c.is_whitespace() && *c != '+')
+            .count();
+        (2..=MAX_BOARD_SIZE)
+            .find(|size| size * size == nb_cells)
+            .and_then(Shape::from_size)
+            .ok_or_else(|| {
+                format!(
+                    "Expected {} cells, found {}",
+                    Shape::default().nb_cells(),
+                    nb_cells
+                )
+            })
+    }
+
+    pub fn box_rows(&self) -> usize {
+        self.box_rows
+    }
+
+    pub fn box_cols(&self) -> usize {
+        self.box_cols
+    }
+
+    /// Whether both diagonals must contain each value exactly once.
+    pub fn diagonals(&self) -> bool {
+        self.diagonals
+    }
+
+    pub fn set_diagonals(&mut self, diagonals: bool) {
+        self.diagonals = diagonals;
+    }
+
+    /// The region map of jigsaw sudoku, with the box of each cell row by row.
+    pub fn regions(&self) -> Option<&[usize]> {
+        self.regions.as_deref()
+    }
+
+    /// Replace the boxes with the regions of the map, which must have `size` orthogonally
+    /// connected cells each. `None` brings the regular boxes back.
+    pub fn set_regions(&mut self, regions: Option<Vec<usize>>) -> std::result::Result<(), String> {
+        if let Some(regions) = &regions {
+            if regions.len() != self.nb_cells() {
+                return Err(format!("A region map needs {} cells", self.nb_cells()));
+            }
+            for region in 0..self.size() {
+                let cells = regions.iter().filter(|r| **r == region).count();
+                if cells != self.size() {
+                    return Err(format!("Region {} has {} cells", region + 1, cells));
+                }
+                if !self.is_connected(regions, region) {
+                    return Err(format!("Region {} isn't connected", region + 1));
+                }
+            }
+        }
+        self.regions = regions;
+        Ok(())
+    }
+
+    /// Whether the cells of the region can all be reached from each other through their sides.
+    pub(crate) fn is_connected(&self, regions: &[usize], region: usize) -> bool {
+        let size = self.size();
+        let mut stack: Vec<usize> = regions
+            .iter()
+            .position(|r| *r == region)
+            .into_iter()
+            .collect();
+        let mut seen = vec![false; regions.len()];
+        let mut nb_seen = 0;
+        while let Some(i) = stack.pop() {
+            if seen[i] {
+                continue;
+            }
+            seen[i] = true;
+            nb_seen += 1;
+            stack.extend(
+                self.neighbors(i / size, i % size)
+                    .map(|(row, col)| row * size + col)
+                    .filter(|j| regions[*j] == region && !seen[*j]),
+            );
+        }
+        nb_seen == regions.iter().filter(|r| **r == region).count()
+    }
+
+    /// The cells sharing a side with the cell.
+    pub fn neighbors(&self, row: usize, col: usize) -> impl Iterator<Item = (usize, usize)> {
+        let size = self.size();
+        [(-1, 0), (1, 0), (0, -1), (0, 1)]
+            .iter()
+            .map(move |(dr, dc)| (row as isize + dr, col as isize + dc))
+            .filter(move |(r, c)| (0..size as isize).contains(r) && (0..size as isize).contains(c))
+            .map(|(r, c)| (r as usize, c as usize))
+    }
+
+    /// The number of rows and columns, which is also the largest value.
+    pub fn size(&self) -> usize {
+        self.box_rows * self.box_cols
+    }
+
+    pub fn nb_cells(&self) -> usize {
+        self.size() * self.size()
+    }
+
+    /// The cells of the board, row by row.
+    pub fn positions(&self) -> impl Iterator<Item = (usize, usize)> {
+        let size = self.size();
+        (0..size * size).map(move |i| (i / size, i % size))
+    }
+
+    pub fn box_index(&self, row: usize, col: usize) -> usize {
+        match &self.regions {
+            Some(regions) => regions[row * self.size() + col],
+            None => (row / self.box_rows) * self.box_rows + (col / self.box_cols),
+        }
+    }
+
+    pub fn units(&self) -> Vec<Unit> {
+        let nb_diagonals = if self.diagonals { 2 } else { 0 };
+        (0..self.size())
+            .map(Unit::Row)
+            .chain((0..self.size()).map(Unit::Column))
+            .chain((0..self.size()).map(Unit::Box))
+            .chain((0..nb_diagonals).map(Unit::Diagonal))
+            .collect()
+    }
+
+    /// The row, column and box of the cell, and the diagonals it is on.
+    pub fn units_containing(&self, row: usize, col: usize) -> Vec<Unit> {
+        let mut units = vec![
+            Unit::Row(row),
+            Unit::Column(col),
+            Unit::Box(self.box_index(row, col)),
+        ];
+        if self.diagonals {
+            units.extend(
+                (0..2)
+                    .map(Unit::Diagonal)
+                    .filter(|unit| self.contains(*unit, row, col)),
+            );
+        }
+        units
+    }
+
+    pub fn cells(&self, unit: Unit) -> Vec<(usize, usize)> {
+        if let (Unit::Box(b), Some(_)) = (unit, &self.regions) {
+            return self
+                .positions()
+                .filter(|(row, col)| self.box_index(*row, *col) == b)
+                .collect();
+        }
+        let (box_rows, box_cols) = (self.box_rows, self.box_cols);
+        (0..self.size())
+            .map(|i| match unit {
+                Unit::Row(row) => (row, i),
+                Unit::Column(col) => (i, col),
+                Unit::Box(b) => (
+                    (b / box_rows) * box_rows + i / box_cols,
+                    (b % box_rows) * box_cols + i % box_cols,
+                ),
+                Unit::Diagonal(0) => (i, i),
+                Unit::Diagonal(_) => (i, self.size() - 1 - i),
+            })
+            .collect()
+    }
+
+    pub fn contains(&self, unit: Unit, row: usize, col: usize) -> bool {
+        match unit {
+            Unit::Row(r) => r == row,
+            Unit::Column(c) => c == col,
+            Unit::Box(b) => b == self.box_index(row, col),
+            Unit::Diagonal(0) => row == col,
+            Unit::Diagonal(_) => row + col == self.size() - 1,
+        }
+    }
+
+    /// Whether two different cells share a unit, and so can't hold the same value.
+    pub fn sees(&self, a: (usize, usize), b: (usize, usize)) -> bool {
+        a != b
+            && self
+                .units_containing(a.0, a.1)
+                .iter()
+                .any(|unit| self.contains(*unit, b.0, b.1))
+    }
+
+    /// The position of the unit in `units`.
+    fn unit_index(&self, unit: Unit) -> usize {
+        match unit {
+            Unit::Row(row) => row,
+            Unit::Column(col) => self.size() + col,
+            Unit::Box(b) => 2 * self.size() + b,
+            Unit::Diagonal(d) => 3 * self.size() + d,
+        }
+    }
+}
+
+/// Format the shape as its number of rows and columns, like `9x9`.
+impl Display for Shape {
+    fn fmt(&self, f: &mut Formatter<'_>) -> Result {
+        write!(f, "{}x{}", self.size(), self.size())
+    }
+}
+
+impl FromStr for Shape {
+    type Err = String;
+
+    fn from_str(s: &str) -> std::result::Result<Self, Self::Err> {
+        let mut sizes = s.splitn(2, ['x', 'X']).map(str::parse::<usize>);
+        match (sizes.next(), sizes.next()) {
+            (Some(Ok(rows)), Some(Ok(cols))) if rows == cols => Shape::from_size(rows),
+            _ => None,
+        }
+        .ok_or_else(|| format!("Unknown size '{}'", s))
+    }
+}
+
+/// A cage of killer sudoku: a group of cells whose values add up to its sum, without repeating a
+/// value. Unlike the units, a cage doesn't need to hold every value.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Cage {
+    cells: Vec<(usize, usize)>,
+    sum: u32,
+}
+
+impl Cage {
+    pub fn new(cells: Vec<(usize, usize)>, sum: u32) -> Cage {
+        Cage { cells, sum }
+    }
+
+    pub fn cells(&self) -> &[(usize, usize)] {
+        &self.cells
+    }
+
+    pub fn sum(&self) -> u32 {
+        self.sum
+    }
+
+    pub fn contains(&self, row: usize, col: usize) -> bool {
+        self.cells.contains(&(row, col))
+    }
+
+    /// The sets of distinct values up to `size` that fill the cage and add up to its sum.
+    pub fn combinations(&self, size: usize) -> Vec<FreeNumberMask> {
+        let mut combinations = vec![];
+        Self::add_combinations(
+            1,
+            size as u8,
+            self.cells.len(),
+            self.sum,
+            FreeNumberMask::none(),
+            &mut combinations,
+        );
+        combinations
+    }
+
+    /// Narrow the candidates of the cells of a cage, given in the order of its cells, to the
+    /// values they take in one of its combinations, each cell holding a different value.
+    pub fn restrict(
+        combinations: &[FreeNumberMask],
+        candidates: &[FreeNumberMask],
+    ) -> Vec<FreeNumberMask> {
+        let mut allowed = vec![FreeNumberMask::none(); candidates.len()];
+        Self::restrict_into(combinations, candidates, &mut allowed);
+        allowed
+    }
+
+    /// Like `restrict`, but write the candidates left to each cell in `allowed` instead of
+    /// allocating them, for the solvers restricting cages at every step.
+    pub fn restrict_into(
+        combinations: &[FreeNumberMask],
+        candidates: &[FreeNumberMask],
+        allowed: &mut [FreeNumberMask],
+    ) {
+        allowed
+            .iter_mut()
+            .for_each(|cell| *cell = FreeNumberMask::none());
+        // a combination is kept if every cell can take one of its values and every value has a
+        // cell to go to
+        let all_candidates = candidates
+            .iter()
+            .fold(FreeNumberMask::none(), |all, cell| all | *cell);
+        let kept = combinations.iter().filter(|combination| {
+            **combination & all_candidates == **combination
+                && candidates.iter().all(|cell| cell.intersects(**combination))
+        });
+
+        // each cell takes a different value of the combination: a value is allowed in a cell when
+        // the other cells can still take the other values, and so are all the values of the cells
+        // found along the way
+        let mut options = [FreeNumberMask::none(); MAX_BOARD_SIZE];
+        for combination in kept {
+            for (cell, cell_candidates) in candidates.iter().enumerate() {
+                let values = *cell_candidates & *combination;
+                for flag in Self::flags(values ^ (values & allowed[cell])) {
+                    for (other, other_candidates) in candidates.iter().enumerate() {
+                        options[other] = *other_candidates & *combination;
+                    }
+                    options[cell] = flag;
+                    let mut owners = [usize::MAX; MAX_BOARD_SIZE];
+                    if (0..candidates.len())
+                        .all(|other| Self::assign(&options, other, &mut 0, &mut owners))
+                    {
+                        for (bit, owner) in owners.iter().enumerate() {
+                            if *owner != usize::MAX {
+                                allowed[*owner] |= FreeNumberMask::from_value(bit as u8 + 1);
+                            }
+                        }
+                    }
+                }
+            }
+        }
+    }
+
+    /// Give the cell one of its options, moving the cells holding the others to other options
+    /// when they can. The owner of each value, by its bit, is in `owners`.
+    fn assign(
+        options: &[FreeNumberMask],
+        cell: usize,
+        visited: &mut u32,
+        owners: &mut [usize; MAX_BOARD_SIZE],
+    ) -> bool {
+        let mut bits = *options[cell] & !*visited;
+        while bits != 0 {
+            let bit = bits.trailing_zeros() as usize;
+            bits &= bits - 1;
+            *visited |= 1 << bit;
+            if owners[bit] == usize::MAX || Self::assign(options, owners[bit], visited, owners) {
+                owners[bit] = cell;
+                return true;
+            }
+        }
+        false
+    }
+
+    /// The values of the mask, one flag at a time.
+    fn flags(mask: FreeNumberMask) -> impl Iterator<Item = FreeNumberMask> {
+        let mut bits: u32 = *mask;
+        std::iter::from_fn(move || {
+            if bits == 0 {
+                return None;
+            }
+            let val = bits.trailing_zeros() as u8 + 1;
+            bits &= bits - 1;
+            Some(FreeNumberMask::from_value(val))
+        })
+    }
+
+    /// Add the combinations of `nb_values` values from `min` to `max` adding up to `sum`.
+    fn add_combinations(
+        min: u8,
+        max: u8,
+        nb_values: usize,
+        sum: u32,
+        values: FreeNumberMask,
+        combinations: &mut Vec<FreeNumberMask>,
+    ) {
+        if nb_values == 0 {
+            if sum == 0 {
+                combinations.push(values);
+            }
+            return;
+        }
+        for val in min..=max {
+            if val as u32 > sum {
+                break;
+            }
+            Self::add_combinations(
+                val + 1,
+                max,
+                nb_values - 1,
+                sum - val as u32,
+                values | FreeNumberMask::from_value(val),
+                combinations,
+            );
+        }
+    }
+}
+
+/// Format the cage as its sum followed by its cells, like `15:r1c1,r1c2`.
+impl Display for Cage {
+    fn fmt(&self, f: &mut Formatter<'_>) -> Result {
+        let cells: Vec<String> = self
+            .cells
+            .iter()
+            .map(|(row, col)| format!("r{}c{}", row + 1, col + 1))
+            .collect();
+        write!(f, "{}:{}", self.sum, cells.join(","))
+    }
+}
+
+impl FromStr for Cage {
+    type Err = String;
+
+    fn from_str(s: &str) -> std::result::Result<Self, Self::Err> {
+        let invalid = || format!("Invalid cage '{}'", s);
+        let mut parts = s.splitn(2, ':');
+        let (sum, cells) = match (parts.next(), parts.next()) {
+            (Some(sum), Some(cells)) => (sum.parse().map_err(|_| invalid())?, cells),
+            _ => return Err(invalid()),
+        };
+        let cells = cells
+            .split(',')
+            .map(|cell| {
+                let mut numbers = cell
+                    .strip_prefix('r')?
+                    .splitn(2, 'c')
+                    .map(str::parse::<usize>);
+                match (numbers.next(), numbers.next()) {
+                    (Some(Ok(row)), Some(Ok(col))) if row > 0 && col > 0 => {
+                        Some((row - 1, col - 1))
+                    }
+                    _ => None,
+                }
+            })
+            .collect::<Option<Vec<(usize, usize)>>>()
+            .ok_or_else(invalid)?;
+        Ok(Cage::new(cells, sum))
+    }
+}
+
+/// Why the board refused a change. Rows and columns are 0-based, and shown 1-based.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum BoardError {
+    /// The row or the column is outside the board.
+    OutOfRange { row: usize, col: usize },
+    /// The value is neither empty (0) nor between 1 and the size of the board.
+    InvalidValue(u8),
+    /// The cell holds a value of the puzzle.
+    FixedValue { row: usize, col: usize },
+    /// The notes can't be changed while the cell holds a value.
+    FilledCell { row: usize, col: usize },
+    /// The value is already in a unit of the cell, in the cell at `row` and `col`.
+    Conflict {
+        val: u8,
+        unit: Unit,
+        row: usize,
+        col: usize,
+    },
+    /// The value is already in the cage of the cell, in the cell at `row` and `col`.
+    CageConflict { val: u8, row: usize, col: usize },
+    /// The values of the cage starting at `row` and `col` can no longer add up to its sum.
+    CageSum { sum: u32, row: usize, col: usize },
+}
+
+impl Display for BoardError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> Result {
+        match *self {
+            BoardError::OutOfRange { row, col } => {
+                write!(f, "No cell at r{}c{}", row + 1, col + 1)
+            }
+            BoardError::InvalidValue(val) => write!(f, "Invalid value {}", val),
+            BoardError::FixedValue { row, col } => {
+                write!(f, "Fixed value at r{}c{}", row + 1, col + 1)
+            }
+            BoardError::FilledCell { row, col } => {
+                write!(f, "Filled cell at r{}c{}", row + 1, col + 1)
+            }
+            BoardError::Conflict {
+                val,
+                unit,
+                row,
+                col,
+            } => write!(
+                f,
+                "{} already in {} at r{}c{}",
+                value_to_char(val),
+                unit,
+                row + 1,
+                col + 1
+            ),
+            BoardError::CageConflict { val, row, col } => write!(
+                f,
+                "{} already in the cage at r{}c{}",
+                value_to_char(val),
+                row + 1,
+                col + 1
+            ),
+            BoardError::CageSum { sum, row, col } => {
+                write!(f, "The cage at r{}c{} adds up to {}", row + 1, col + 1, sum)
+            }
+        }
+    }
+}
+
+impl std::error::Error for BoardError {}
+
+impl From<BoardError> for String {
+    fn from(error: BoardError) -> Self {
+        error.to_string()
+    }
+}
+
+#[derive(Clone)]
 pub struct Board {
-    free_number_rows: [FreeNumberMask; BOARD_SIZE],
-    free_number_columns: [FreeNumberMask; BOARD_SIZE],
-    free_number_boxes: [FreeNumberMask; BOARD_SIZE],
-    cells: [[Cell; BOARD_SIZE]; BOARD_SIZE],
+    shape: Shape,
+    cells: Vec<Vec<Cell>>,
+    // the values missing from each unit, in the order of `Shape::units`
+    free_numbers: Vec<FreeNumberMask>,
+    // how many times each value appears in each unit, which can be more than once when conflicts
+    // are allowed
+    counts: Vec<Vec<u8>>,
+    allow_conflicts: bool,
+    auto_notes: bool,
+    removed_notes: Vec<Vec<Vec<(usize, usize)>>>,
+    cages: Vec<Cage>,
+    // the index of the cage of each cell, in killer sudoku
+    cage_index: Vec<Vec<Option<usize>>>,
+}
+
+impl Default for Board {
+    fn default() -> Self {
+        Board::with_shape(Shape::default())
+    }
 }
 
 impl Board {
@@ -147,6 +798,114 @@ impl Board {
         Board::default()
     }
 
+    /// An empty board of the given shape.
+    pub fn with_shape(shape: Shape) -> Board {
+        let size = shape.size();
+        let nb_units = shape.units().len();
+        Board {
+            cells: vec![vec![Cell::default(); size]; size],
+            free_numbers: vec![FreeNumberMask::up_to(size); nb_units],
+            counts: vec![vec![0; size + 1]; nb_units],
+            allow_conflicts: false,
+            auto_notes: false,
+            removed_notes: vec![vec![vec![]; size]; size],
+            cages: vec![],
+            cage_index: vec![vec![None; size]; size],
+            shape,
+        }
+    }
+
+    /// Parse the one-line format for a board of the given shape, which the number of cells can't
+    /// tell apart from the other shapes of the same size, like the diagonal variant.
+    pub fn from_str_with_shape(s: &str, shape: Shape) -> std::result::Result<Self, String> {
+        // (value, fixed) of each cell
+        let mut values: Vec<(u8, bool)> = vec![];
+        let mut free = false;
+        for c in s.chars().filter(|c| !c.is_whitespace()) {
+            match (c, free) {
+                ('+', false) => {
+                    free = true;
+                    continue;
+                }
+                ('.', false) | ('0', false) => values.push((0, true)),
+                _ => match char_to_value(c) {
+                    Some(val) => values.push((val, !free)),
+                    None => return Err(format!("Invalid character '{}'", c)),
+                },
+            }
+            free = false;
+        }
+        if free {
+            return Err(String::from("Invalid character '+'"));
+        }
+
+        if values.len() != shape.nb_cells() {
+            return Err(format!(
+                "Expected {} cells, found {}",
+                shape.nb_cells(),
+                values.len()
+            ));
+        }
+
+        // the givens first, then the free values, which may conflict like the player's entries
+        let mut board = Board::with_shape(shape);
+        for fixed in [true, false].iter() {
+            board.set_allow_conflicts(!fixed);
+            for (i, (val, _)) in values.iter().enumerate().filter(|(_, v)| v.1 == *fixed) {
+                let (row, col) = (i / board.size(), i % board.size());
+                board
+                    .set_value(row, col, *val)
+                    .map_err(|e| format!("{} (r{}c{})", e, row + 1, col + 1))?;
+            }
+            if *fixed {
+                board.freeze();
+            }
+        }
+        board.set_allow_conflicts(false);
+        Ok(board)
+    }
+
+    pub fn shape(&self) -> &Shape {
+        &self.shape
+    }
+
+    /// The number of rows and columns, which is also the largest value.
+    pub fn size(&self) -> usize {
+        self.shape.size()
+    }
+
+    /// The cages of killer sudoku, empty for the other variants.
+    pub fn cages(&self) -> &[Cage] {
+        &self.cages
+    }
+
+    /// Replace the cages. A cell can't be in two cages, but can be in none.
+    pub fn set_cages(&mut self, cages: Vec<Cage>) -> std::result::Result<(), String> {
+        let mut cage_index = vec![vec![None; self.size()]; self.size()];
+        for (index, cage) in cages.iter().enumerate() {
+            for &(row, col) in cage.cells() {
+                self.check_cell(row, col)?;
+                if cage_index[row][col].replace(index).is_some() {
+                    return Err(format!("r{}c{} is in two cages", row + 1, col + 1));
+                }
+            }
+            if cage.combinations(self.size()).is_empty() {
+                return Err(format!(
+                    "{} cells can't add up to {}",
+                    cage.cells().len(),
+                    cage.sum()
+                ));
+            }
+        }
+        self.cages = cages;
+        self.cage_index = cage_index;
+        Ok(())
+    }
+
+    pub fn get_cage(&self, row: usize, col: usize) -> Option<&Cage> {
+        self.cage_index[row][col].map(|index| &self.cages[index])
+    }
+
     pub fn freeze(&mut self) {
         for row in self.cells.iter_mut() {
             for cell in row.iter_mut() {
@@ -159,13 +918,34 @@ impl Board {
     }
 
     pub fn reset(&mut self) {
-        for row in self.cells.iter_mut() {
-            for cell in row.iter_mut() {
-                if let Free(_) = *cell {
-                    *cell = Free(0.into());
-                }
+        for (row, col) in self.shape.positions() {
+            if let Free(_, _) = self.cells[row][col] {
+                self.clear_value(row, col).unwrap();
+                self.cells[row][col] = Cell::default();
             }
         }
+        for row in self.removed_notes.iter_mut() {
+            row.iter_mut().for_each(Vec::clear);
+        }
+    }
+
+    /// Whether a value can be set when its row, column, box or cage already contains it, or when
+    /// it keeps its cage from adding up to its sum.
+    pub fn allow_conflicts(&self) -> bool {
+        self.allow_conflicts
+    }
+
+    pub fn set_allow_conflicts(&mut self, allow_conflicts: bool) {
+        self.allow_conflicts = allow_conflicts;
+    }
+
+    /// Whether placing a value removes it from the notes of the peers of the cell.
+    pub fn auto_notes(&self) -> bool {
+        self.auto_notes
+    }
+
+    pub fn set_auto_notes(&mut self, auto_notes: bool) {
+        self.auto_notes = auto_notes;
     }
 
     pub fn get_value(&self, row: usize, col: usize) -> Option<u8> {
@@ -190,78 +970,332 @@ impl Board {
         row: usize,
         col: usize,
         val: u8,
-    ) -> std::result::Result<(), String> {
+    ) -> std::result::Result<(), BoardError> {
+        self.check_cell(row, col)?;
+        if val as usize > self.size() {
+            return Err(BoardError::InvalidValue(val));
+        }
+
         if self.is_fixed_value(row, col) {
-            return Err(String::from("Fixed value"));
+            return Err(BoardError::FixedValue { row, col });
+        }
+
+        // the value would conflict with itself
+        if val != 0 && self.get_value(row, col) == Some(val) {
+            return Ok(());
         }
 
-        if !self.can_set_value(row, col, val) {
-            return Err(String::from("Forbidden value"));
+        if !self.allow_conflicts {
+            let conflict = if self.can_set_value(row, col, val) {
+                None
+            } else {
+                self.find_conflict(row, col, val)
+            };
+            if let Some(error) = conflict.or_else(|| self.find_cage_conflict(row, col, val)) {
+                return Err(error);
+            }
         }
 
         self.clear_value(row, col)?;
-        self.cells[row][col] = Cell::new(val, false);
+        self.cells[row][col] = Free(val.into(), self.get_notes(row, col));
 
         if val != 0 {
-            let flag = FreeNumberFlags::from(val as u16);
-            self.free_number_rows[row].unset(flag);
-            self.free_number_columns[col].unset(flag);
-            self.free_number_boxes[Board::compute_box_index(row, col)].unset(flag);
+            self.count_value(row, col, val, true);
+
+            if self.auto_notes {
+                self.remove_notes(row, col, val);
+            }
         }
 
         Ok(())
     }
 
-    pub fn clear_value(&mut self, row: usize, col: usize) -> std::result::Result<(), String> {
+    pub fn clear_value(&mut self, row: usize, col: usize) -> std::result::Result<(), BoardError> {
+        self.check_cell(row, col)?;
         if self.is_fixed_value(row, col) {
-            return Err(String::from("Fixed value"));
+            return Err(BoardError::FixedValue { row, col });
         }
 
         if let Some(val) = self.get_value(row, col) {
-            let flag = FreeNumberFlags::from(val as u16);
-            self.free_number_rows[row].set(flag);
-            self.free_number_columns[col].set(flag);
-            self.free_number_boxes[Board::compute_box_index(row, col)].set(flag);
-            self.cells[row][col] = Cell::new(0, false);
+            self.count_value(row, col, val, false);
+            self.cells[row][col] = Free(0.into(), self.get_notes(row, col));
+            self.removed_notes[row][col].clear();
         }
 
         Ok(())
     }
 
+    /// Whether the value of the cell is also in its row, column, box or cage, or keeps its cage
+    /// from adding up to its sum.
+    pub fn is_conflict(&self, row: usize, col: usize) -> bool {
+        match self.get_value(row, col) {
+            Some(val) => {
+                self.shape
+                    .units_containing(row, col)
+                    .into_iter()
+                    .any(|unit| self.counts[self.shape.unit_index(unit)][val as usize] > 1)
+                    || self.find_cage_conflict(row, col, val).is_some()
+            }
+            None => false,
+        }
+    }
+
+    pub fn has_conflicts(&self) -> bool {
+        self.shape
+            .positions()
+            .any(|(row, col)| self.is_conflict(row, col))
+    }
+
+    /// The peers whose notes lost the value of the cell when it was placed.
+    pub fn get_removed_notes(&self, row: usize, col: usize) -> &[(usize, usize)] {
+        &self.removed_notes[row][col]
+    }
+
+    /// The notes of the cell, always empty for a fixed cell.
+    pub fn get_notes(&self, row: usize, col: usize) -> FreeNumberMask {
+        match self.cells[row][col] {
+            Fixed(_) => FreeNumberMask::none(),
+            Free(_, notes) => notes,
+        }
+    }
+
+    /// Replace the notes of an empty cell.
+    pub fn set_notes(
+        &mut self,
+        row: usize,
+        col: usize,
+        notes: FreeNumberMask,
+    ) -> std::result::Result<(), BoardError> {
+        self.check_cell(row, col)?;
+        match self.cells[row][col] {
+            Fixed(_) => Err(BoardError::FixedValue { row, col }),
+            Free(val, _) if Into::<u8>::into(val) != 0 => Err(BoardError::FilledCell { row, col }),
+            Free(val, _) => {
+                self.cells[row][col] = Free(val, notes);
+                Ok(())
+            }
+        }
+    }
+
+    /// Add the value to the notes of an empty cell, or remove it if already there.
+    pub fn toggle_note(
+        &mut self,
+        row: usize,
+        col: usize,
+        val: u8,
+    ) -> std::result::Result<(), BoardError> {
+        self.check_cell(row, col)?;
+        if val == 0 || val as usize > self.size() {
+            return Err(BoardError::InvalidValue(val));
+        }
+        let notes = self.get_notes(row, col) ^ FreeNumberMask::from_value(val);
+        self.set_notes(row, col, notes)
+    }
+
+    pub fn has_notes(&self) -> bool {
+        self.shape
+            .positions()
+            .any(|(row, col)| !self.get_notes(row, col).is_none())
+    }
+
     pub fn get_available_values(&self, row: usize, col: usize) -> Vec<u8> {
-        let free_values = self.free_number_rows[row]
-            & self.free_number_columns[col]
-            & self.free_number_boxes[Board::compute_box_index(row, col)];
+        self.get_candidates(row, col).values()
+    }
 
-        (1..=BOARD_SIZE as u8)
-            .into_iter()
-            .filter(|d| free_values.contains(FreeNumberFlags::from(*d as u16)))
-            .collect()
+    /// The values that can be set in the cell without breaking a row, column or box, nor its
+    /// cage given the values the other cells of the cage can still take.
+    pub fn get_candidates(&self, row: usize, col: usize) -> FreeNumberMask {
+        let candidates = self.get_unit_candidates(row, col);
+        let cage = match self.get_cage(row, col) {
+            Some(cage) => cage,
+            None => return candidates,
+        };
+
+        let cage_candidates: Vec<FreeNumberMask> = cage
+            .cells()
+            .iter()
+            .map(|&(r, c)| match self.get_value(r, c) {
+                _ if (r, c) == (row, col) => candidates,
+                Some(val) => FreeNumberMask::from_value(val),
+                None => self.get_unit_candidates(r, c),
+            })
+            .collect();
+        let allowed = Cage::restrict(&cage.combinations(self.size()), &cage_candidates);
+        let index = cage.cells().iter().position(|cell| *cell == (row, col));
+        allowed[index.unwrap()]
     }
 
     pub fn is_solved(&self) -> bool {
-        self.free_number_rows
-            .iter()
-            .all(|mask| mask.count_ones() == 0)
+        // the units being complete, every cell is filled
+        self.free_numbers.iter().all(|mask| mask.count_ones() == 0)
+            && self.cages.iter().all(|cage| {
+                cage.cells().iter().all(|&(row, col)| {
+                    let val = self.get_value(row, col).unwrap();
+                    self.find_cage_conflict(row, col, val).is_none()
+                })
+            })
+    }
+
+    /// Format the free values in the one-line format, with `.` for the fixed and empty cells.
+    pub fn to_entries_string(&self) -> String {
+        self.shape
+            .positions()
+            .map(|(row, col)| match self.get_value(row, col) {
+                Some(val) if !self.is_fixed_value(row, col) => value_to_char(val),
+                _ => '.',
+            })
+            .collect()
+    }
+
+    /// Count the value in the units of the cell, a value being free while its count is zero.
+    fn count_value(&mut self, row: usize, col: usize, val: u8, placed: bool) {
+        let flag = FreeNumberFlags::from(val as u16);
+        for unit in self.shape.units_containing(row, col) {
+            let index = self.shape.unit_index(unit);
+            let count = &mut self.counts[index][val as usize];
+            let free = &mut self.free_numbers[index];
+            if placed {
+                *count += 1;
+                free.unset(flag);
+            } else {
+                *count -= 1;
+                if *count == 0 {
+                    free.set(flag);
+                }
+            }
+        }
+    }
+
+    fn remove_notes(&mut self, row: usize, col: usize, val: u8) {
+        let flag = FreeNumberMask::from_value(val);
+        for unit in self.shape.units_containing(row, col) {
+            for (peer_row, peer_col) in self.shape.cells(unit) {
+                if let Free(peer_val, ref mut notes) = self.cells[peer_row][peer_col] {
+                    if notes.contains(flag) && Into::<u8>::into(peer_val) == 0 {
+                        notes.unset(flag);
+                        self.removed_notes[row][col].push((peer_row, peer_col));
+                    }
+                }
+            }
+        }
+    }
+
+    fn check_cell(&self, row: usize, col: usize) -> std::result::Result<(), BoardError> {
+        if row >= self.size() || col >= self.size() {
+            return Err(BoardError::OutOfRange { row, col });
+        }
+        Ok(())
+    }
+
+    /// The first unit of the cell already holding the value, and where.
+    fn find_conflict(&self, row: usize, col: usize, val: u8) -> Option<BoardError> {
+        self.shape
+            .units_containing(row, col)
+            .into_iter()
+            .flat_map(|unit| {
+                self.shape
+                    .cells(unit)
+                    .into_iter()
+                    .map(move |cell| (unit, cell))
+            })
+            .find(|(_, cell)| *cell != (row, col) && self.get_value(cell.0, cell.1) == Some(val))
+            .map(|(unit, (row, col))| BoardError::Conflict {
+                val,
+                unit,
+                row,
+                col,
+            })
+    }
+
+    /// Why the value can't be in the cell given the other values of its cage: it is already
+    /// there, or the cage can no longer add up to its sum.
+    fn find_cage_conflict(&self, row: usize, col: usize, val: u8) -> Option<BoardError> {
+        let cage = self.get_cage(row, col)?;
+        if val == 0 {
+            return None;
+        }
+
+        let (mut total, mut nb_empty) = (val as u32, 0);
+        for &(r, c) in cage.cells().iter().filter(|cell| **cell != (row, col)) {
+            match self.get_value(r, c) {
+                Some(other) if other == val => {
+                    return Some(BoardError::CageConflict {
+                        val,
+                        row: r,
+                        col: c,
+                    })
+                }
+                Some(other) => total += other as u32,
+                None => nb_empty += 1,
+            }
+        }
+
+        if total > cage.sum() || (nb_empty == 0 && total != cage.sum()) {
+            let (row, col) = cage.cells()[0];
+            return Some(BoardError::CageSum {
+                sum: cage.sum(),
+                row,
+                col,
+            });
+        }
+        None
+    }
+
+    /// The values missing from every unit of the cell.
+    fn get_unit_candidates(&self, row: usize, col: usize) -> FreeNumberMask {
+        self.shape
+            .units_containing(row, col)
+            .into_iter()
+            .fold(FreeNumberMask::up_to(self.size()), |mask, unit| {
+                mask & self.free_numbers[self.shape.unit_index(unit)]
+            })
     }
 
     fn can_set_value(&self, row: usize, col: usize, val: u8) -> bool {
         let flag = FreeNumberFlags::from(val as u16);
 
-        self.free_number_rows[row].contains(flag)
-            && self.free_number_columns[col].contains(flag)
-            && self.free_number_boxes[Board::compute_box_index(row, col)].contains(flag)
+        self.shape
+            .units_containing(row, col)
+            .into_iter()
+            .all(|unit| self.free_numbers[self.shape.unit_index(unit)].contains(flag))
+    }
+}
+
+/// Parse the one-line format: one character per cell, row by row, with a digit or a letter for
+/// the fixed values and `.` or `0` for the empty cells. A `+` before a value makes it free, as
+/// written by the alternate format. The size of the board is given by the number of cells.
+/// Whitespaces are ignored.
+impl FromStr for Board {
+    type Err = String;
+
+    fn from_str(s: &str) -> std::result::Result<Self, Self::Err> {
+        Board::from_str_with_shape(s, Shape::from_one_line(s)?)
     }
+}
 
-    fn compute_box_index(row: usize, col: usize) -> usize {
-        (row / BOARD_BOX_SIZE) * BOARD_BOX_SIZE + (col / BOARD_BOX_SIZE)
+/// Format the board in the one-line format. Only the fixed values are written, so that parsing
+/// the result gives back the puzzle; the alternate flag (`{:#}`) writes the free values as well,
+/// each after a `+`, so that parsing the result gives back the board with the same free values.
+impl Display for Board {
+    fn fmt(&self, f: &mut Formatter<'_>) -> Result {
+        for (row, col) in self.shape.positions() {
+            match self.get_value(row, col) {
+                Some(val) if self.is_fixed_value(row, col) => write!(f, "{}", value_to_char(val))?,
+                Some(val) if f.alternate() => write!(f, "+{}", value_to_char(val))?,
+                _ => write!(f, ".")?,
+            }
+        }
+        Result::Ok(())
     }
 }
 
 #[cfg(test)]
 mod tests {
 
-    use crate::board::{Board, FreeNumberFlags};
+    use crate::board::{
+        char_to_value, value_to_char, Board, BoardError, Cage, FreeNumberFlags, FreeNumberMask,
+        Shape, Unit, BOARD_SIZE,
+    };
     use crate::solver::{SimpleSolver, Solver};
 
     #[test]
@@ -282,12 +1316,310 @@ mod tests {
     }
 
     #[test]
-    fn test_compute_box_index() {
+    fn test_box_index() {
+        let shape = Shape::default();
         (0..3usize).into_iter().for_each(|row| {
             (0..3usize).into_iter().for_each(|col| {
-                assert_eq!(0, Board::compute_box_index(row, col));
+                assert_eq!(0, shape.box_index(row, col));
             })
-        })
+        });
+        assert_eq!(15, Shape::new(4).box_index(15, 15));
+
+        let shape = Shape::with_boxes(2, 3);
+        assert_eq!(1, shape.box_index(1, 3));
+        assert_eq!(2, shape.box_index(2, 0));
+        assert_eq!(5, shape.box_index(5, 5));
+        assert_eq!((2, 3), shape.cells(Unit::Box(3))[0]);
+    }
+
+    #[test]
+    fn test_unit_cells() {
+        let shapes = [4, 6, 8, 9, 12, 16]
+            .iter()
+            .map(|size| Shape::from_size(*size));
+        for shape in shapes.map(Option::unwrap) {
+            shape.units().into_iter().for_each(|unit| {
+                let cells = shape.cells(unit);
+                assert!(cells
+                    .iter()
+                    .all(|(row, col)| *row < shape.size() && *col < shape.size()));
+                assert_eq!(shape.size(), cells.len());
+                assert!(cells
+                    .iter()
+                    .all(|(row, col)| shape.contains(unit, *row, *col)));
+            });
+        }
+        assert_eq!((3, 3), Shape::default().cells(Unit::Box(4))[0]);
+        assert!(Shape::default().sees((0, 0), (2, 2)));
+        assert!(!Shape::default().sees((0, 0), (3, 3)));
+    }
+
+    #[test]
+    fn test_regions() {
+        let regions = vec![0, 0, 0, 1, 0, 2, 1, 1, 2, 2, 3, 1, 2, 3, 3, 3];
+        let mut shape = Shape::new(2);
+        shape.set_regions(Some(regions.clone())).unwrap();
+        assert_eq!(2, shape.box_index(1, 1));
+        assert_eq!(
+            vec![(1, 1), (2, 0), (2, 1), (3, 0)],
+            shape.cells(Unit::Box(2))
+        );
+        assert!(shape.sees((0, 2), (1, 0)));
+        assert!(!shape.sees((0, 0), (1, 1)));
+
+        let mut split = regions.clone();
+        split.swap(0, 15);
+        assert_eq!(
+            Err(String::from("Region 1 isn't connected")),
+            shape.set_regions(Some(split))
+        );
+        assert_eq!(
+            Err(String::from("A region map needs 16 cells")),
+            shape.set_regions(Some(regions[..15].to_vec()))
+        );
+        assert_eq!(regions, shape.regions().unwrap());
+    }
+
+    #[test]
+    fn test_shape_from_str() {
+        assert_eq!(Ok(Shape::new(4)), "16x16".parse());
+        assert_eq!(Ok(Shape::with_boxes(2, 3)), "6x6".parse());
+        assert_eq!(Some(Shape::with_boxes(3, 4)), Shape::from_size(12));
+        assert_eq!(None, Shape::from_size(7));
+        assert_eq!("4x4", Shape::new(2).to_string());
+        assert!("11x11".parse::<Shape>().is_err());
+        assert!("9x6".parse::<Shape>().is_err());
+    }
+
+    #[test]
+    fn test_value_chars() {
+        (1..=25u8).for_each(|val| assert_eq!(Some(val), char_to_value(value_to_char(val))));
+        assert_eq!('G', value_to_char(16));
+        assert_eq!(Some(16), char_to_value('g'));
+        assert_eq!(None, char_to_value('0'));
+    }
+
+    #[test]
+    fn test_parse_and_format() {
+        let puzzle =
+            "53..7....6..195....98....6.8...6...34..8.3..17...2...6.6....28....419..5....8..79";
+        let mut board: Board = puzzle.replace(".", "0").parse().unwrap();
+        assert!(board.is_fixed_value(0, 0));
+        assert!(!board.is_fixed_value(0, 2));
+        assert_eq!(Some(5), board.get_value(0, 0));
+
+        board.set_value(0, 2, 4).unwrap();
+        assert_eq!(puzzle, board.to_string());
+        assert_eq!(puzzle.replacen(".", "+4", 1), format!("{:#}", board));
+
+        let entered: Board = format!("{:#}", board).parse().unwrap();
+        assert!(entered.is_fixed_value(0, 0));
+        assert!(!entered.is_fixed_value(0, 2));
+        assert_eq!(Some(4), entered.get_value(0, 2));
+        assert_eq!(format!("{:#}", board), format!("{:#}", entered));
+        assert!(puzzle.replacen(".", "+.", 1).parse::<Board>().is_err());
+
+        assert_eq!(
+            format!("..4{}", ".".repeat(BOARD_SIZE * BOARD_SIZE - 3)),
+            board.to_entries_string()
+        );
+
+        let board: Board = board.to_string().parse().unwrap();
+        assert!(!board.is_fixed_value(0, 2));
+        assert_eq!(None, board.get_value(0, 2));
+
+        let board: Board = "1.3..4....2..1.4".parse().unwrap();
+        assert_eq!(4, board.size());
+        assert_eq!("1.3..4....2..1.4", board.to_string());
+
+        let mut board = Board::with_shape(Shape::new(4));
+        board.set_value(0, 0, 16).unwrap();
+        assert_eq!(format!("G{}", ".".repeat(255)), board.to_entries_string());
+    }
+
+    #[test]
+    fn test_parse_errors() {
+        assert!("123".parse::<Board>().is_err());
+        assert!("x".repeat(81).parse::<Board>().is_err());
+        assert!("11".repeat(81).parse::<Board>().is_err());
+        assert!(format!("11{}", ".".repeat(79)).parse::<Board>().is_err());
+        assert!(format!("A{}", ".".repeat(80)).parse::<Board>().is_err());
+    }
+
+    #[test]
+    fn test_errors() {
+        let mut board = Board::new();
+        board.set_value(4, 3, 7).unwrap();
+        board.freeze();
+
+        let error = board.set_value(3, 5, 7).unwrap_err();
+        assert_eq!(
+            BoardError::Conflict {
+                val: 7,
+                unit: Unit::Box(4),
+                row: 4,
+                col: 3
+            },
+            error
+        );
+        assert_eq!("7 already in box 5 at r5c4", error.to_string());
+        assert_eq!(
+            Err(BoardError::FixedValue { row: 4, col: 3 }),
+            board.clear_value(4, 3)
+        );
+        assert_eq!(
+            Err(BoardError::OutOfRange { row: 9, col: 0 }),
+            board.set_value(9, 0, 1)
+        );
+        assert_eq!(Err(BoardError::InvalidValue(10)), board.set_value(0, 0, 10));
+        assert_eq!(Err(BoardError::InvalidValue(0)), board.toggle_note(0, 0, 0));
+
+        // setting the value a cell already holds changes nothing
+        board.set_value(3, 5, 6).unwrap();
+        assert_eq!(Ok(()), board.set_value(3, 5, 6));
+        assert_eq!(Some(6), board.get_value(3, 5));
+    }
+
+    #[test]
+    fn test_notes() {
+        let mut board = Board::new();
+        board.set_value(0, 0, 5).unwrap();
+        board.freeze();
+        assert!(!board.has_notes());
+        assert!(board.toggle_note(0, 0, 1).is_err());
+
+        board.toggle_note(0, 1, 1).unwrap();
+        board.toggle_note(0, 1, 2).unwrap();
+        board.toggle_note(0, 1, 1).unwrap();
+        assert_eq!(vec![2], board.get_notes(0, 1).values());
+        assert!(board.has_notes());
+
+        board.set_value(0, 1, 3).unwrap();
+        assert!(board.toggle_note(0, 1, 4).is_err());
+        board.clear_value(0, 1).unwrap();
+        assert_eq!(vec![2], board.get_notes(0, 1).values());
+
+        board.reset();
+        assert!(!board.has_notes());
+    }
+
+    #[test]
+    fn test_auto_notes() {
+        let mut board = Board::new();
+        board.toggle_note(0, 8, 7).unwrap();
+        board.toggle_note(1, 1, 7).unwrap();
+        board.toggle_note(8, 0, 7).unwrap();
+        board.toggle_note(4, 4, 7).unwrap();
+        board.toggle_note(4, 4, 5).unwrap();
+
+        board.set_value(0, 0, 7).unwrap();
+        assert!(board.get_removed_notes(0, 0).is_empty());
+
+        board.set_auto_notes(true);
+        board.clear_value(0, 0).unwrap();
+        board.set_value(0, 0, 7).unwrap();
+        assert_eq!(&[(0, 8), (8, 0), (1, 1)], board.get_removed_notes(0, 0));
+        assert!(board.get_notes(0, 8).is_none());
+        assert_eq!(vec![5, 7], board.get_notes(4, 4).values());
+
+        board.clear_value(0, 0).unwrap();
+        assert!(board.get_removed_notes(0, 0).is_empty());
+    }
+
+    #[test]
+    fn test_conflicts() {
+        let mut board = Board::new();
+        board.set_value(0, 0, 5).unwrap();
+        assert!(board.set_value(0, 8, 5).is_err());
+
+        board.set_allow_conflicts(true);
+        board.set_value(0, 8, 5).unwrap();
+        board.set_value(1, 1, 5).unwrap();
+        assert!(board.is_conflict(0, 0));
+        assert!(board.is_conflict(0, 8));
+        assert!(board.is_conflict(1, 1));
+        assert!(!board.is_conflict(2, 2));
+
+        board.clear_value(0, 0).unwrap();
+        assert!(!board.is_conflict(0, 8));
+        assert!(!board.get_candidates(0, 4).contains_value(5));
+        board.clear_value(0, 8).unwrap();
+        assert!(board.get_candidates(0, 4).contains_value(5));
+        assert!(!board.has_conflicts());
+
+        board.reset();
+        assert_eq!(BOARD_SIZE, board.get_available_values(1, 1).len());
+    }
+
+    #[test]
+    fn test_diagonals() {
+        let mut shape = Shape::default();
+        shape.set_diagonals(true);
+        assert_eq!(29, shape.units().len());
+        assert_eq!(5, shape.units_containing(4, 4).len());
+        assert!(shape.sees((0, 8), (8, 0)));
+
+        let mut board = Board::with_shape(shape);
+        board.set_value(0, 0, 5).unwrap();
+        let error = board.set_value(8, 8, 5).unwrap_err();
+        assert_eq!("5 already in diagonal 1 at r1c1", error.to_string());
+        assert!(!board.get_candidates(4, 4).contains_value(5));
+        assert!(board.get_candidates(4, 5).contains_value(5));
+    }
+
+    #[test]
+    fn test_cages() {
+        let cage = "3:r1c1,r1c2".parse::<Cage>().unwrap();
+        assert_eq!(&[(0, 0), (0, 1)], cage.cells());
+        assert_eq!("3:r1c1,r1c2", cage.to_string());
+        assert_eq!(
+            vec![FreeNumberMask::from_value(1) | FreeNumberMask::from_value(2)],
+            cage.combinations(BOARD_SIZE)
+        );
+        assert!("3:r1c1,r0c2".parse::<Cage>().is_err());
+
+        let mut board = Board::new();
+        let other = Cage::new(vec![(0, 2), (3, 3)], 10);
+        assert!(board
+            .set_cages(vec![cage.clone(), Cage::new(vec![(0, 1)], 5)])
+            .is_err());
+        assert!(board.set_cages(vec![Cage::new(vec![(0, 0)], 10)]).is_err());
+        board.set_cages(vec![cage, other.clone()]).unwrap();
+        assert_eq!(Some(&other), board.get_cage(3, 3));
+        assert_eq!(vec![1, 2], board.get_available_values(0, 1));
+        assert_eq!(
+            vec![1, 2, 3, 4, 6, 7, 8, 9],
+            board.get_available_values(0, 2)
+        );
+
+        board.set_value(0, 2, 3).unwrap();
+        let error = board.set_value(3, 3, 3).unwrap_err();
+        assert_eq!("3 already in the cage at r1c3", error.to_string());
+        let error = board.set_value(3, 3, 8).unwrap_err();
+        assert_eq!("The cage at r1c3 adds up to 10", error.to_string());
+        assert_eq!(vec![7], board.get_available_values(3, 3));
+
+        board.set_allow_conflicts(true);
+        board.set_value(3, 3, 8).unwrap();
+        assert!(board.is_conflict(3, 3));
+        assert!(board.is_conflict(0, 2));
+        board.set_value(3, 3, 7).unwrap();
+        assert!(!board.has_conflicts());
+    }
+
+    #[test]
+    fn test_is_solved_cages() {
+        let mut board = Board::new();
+        SimpleSolver::new().solve(&mut board);
+        let val = board.get_value(0, 0).unwrap() as u32;
+        board.set_cages(vec![Cage::new(vec![(0, 0)], val)]).unwrap();
+        assert!(board.is_solved());
+
+        // another sum, still within 1 to 9 so that the cage is accepted
+        board
+            .set_cages(vec![Cage::new(vec![(0, 0)], val % 9 + 1)])
+            .unwrap();
+        assert!(!board.is_solved());
     }
 
     #[test]
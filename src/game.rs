@@ -1,24 +1,76 @@
-use crate::board::Board;
-use crate::generator::{BasicGenerator, Difficulty, Generator};
+use crate::board::{Board, FreeNumberMask, Shape};
+use crate::generator::{BasicGenerator, Difficulty, Generator, KillerGenerator, RegionGenerator};
 use crate::input;
+use crate::rating::Rating;
 use crate::render::{ConsoleRender, Render};
-use crate::solver::{SimpleSolver, Solver};
+use crate::save::{self, SavedGame};
+use crate::solver::{DlxSolver, LogicalSolver, PropagationSolver, Solver};
+use rand::{thread_rng, Rng};
+use std::fs;
+use std::path::Path;
 use std::time::Instant;
 
+/// File used by `save` and `resume` when no path is given.
+pub const SAVE_PATH: &str = "sudoku.save";
+
 const HELP: &'static [&'static str] = &[
     "   <R> <C> <V>: Set the value V in the cell at row R and column C.",
     " clear <R> <C>: Clear the value in the cell at row R and column C.",
-    "       new <D>: Start a new sudoku with difficulty D in [easy, medium, hard, expert].",
+    "    new <ARGS>: Start a new sudoku, with ARGS: [killer] [x] [jigsaw] [SIZE] <D> [S].",
+    "                killer: cages with sums instead of givens, up to 9x9.",
+    "                x: the diagonals hold every value too.",
+    "                jigsaw: irregular regions instead of the boxes, up to 12x12.",
+    "                SIZE: 4x4, 6x6, 8x8, 9x9 (default), 12x12, 16x16 or 25x25.",
+    "                D: the difficulty in [easy, medium, hard, expert]. S: the seed.",
+    "                The variants and the size come in any order, before D.",
+    "      load <P>: Load the puzzle P, given as a file path or as a string with a character",
+    "                per cell, row after row: 81 for 9x9, from 16 for 4x4 to 625 for 25x25.",
+    "        export: Show the givens and your entries as strings with a character per cell.",
+    "      save [P]: Save the current game to the file P (default: sudoku.save).",
+    "    resume [P]: Resume the game saved in the file P (default: sudoku.save).",
+    "   note <ARGS>: Edit the notes, with ARGS one of:",
+    "                <R> <C> <V> to add or remove the note V in the cell at row R and column C,",
+    "                clear [R C] to clear the notes of the cell, or of every cell,",
+    "                fill [R C] to note the possible values of the cell, or of every cell,",
+    "                restore to put back the notes removed by the value just cleared.",
+    "      view <V>: Draw the empty cells with V in [values, notes, candidates].",
+    "   set <S> [B]: Turn the setting S on or off, or show it, with S in [autonotes, strict].",
     "         reset: Reset the current sudoku.",
+    "      undo [N]: Undo the last N moves (default: 1).",
+    "      redo [N]: Redo the last N undone moves (default: 1).",
+    "         check: Highlight your wrong entries.",
+    "     lives [N]: End the game after N mistakes, or never with off.",
+    "          hint: Show the next logical step.",
     "         solve: Solve the current sudoku.",
     "          quit: Quit the game.",
 ];
 
+/// How the empty cells are drawn: blank, or with the player's notes or the possible values.
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub enum View {
+    Values,
+    Notes,
+    Candidates,
+}
+
 pub struct Game {
     board: Board,
+    solution: Board,
     message: String,
     start_time: Instant,
     quit: bool,
+    highlights: Vec<(usize, usize)>,
+    view: View,
+    auto_notes: bool,
+    strict: bool,
+    restorable_notes: Option<(u8, Vec<(usize, usize)>)>,
+    undo_history: Vec<Board>,
+    redo_history: Vec<Board>,
+    difficulty: Option<Difficulty>,
+    score: Option<f32>,
+    seed: Option<u64>,
+    mistakes: usize,
+    max_mistakes: Option<usize>,
     headers: Vec<String>,
     footers: Vec<String>,
 }
@@ -27,13 +79,28 @@ impl Game {
     pub fn new() -> Game {
         let mut game = Game {
             board: Board::new(),
+            solution: Board::new(),
             message: String::from("Welcome"),
             start_time: Instant::now(),
             quit: false,
+            highlights: vec![],
+            view: View::Values,
+            auto_notes: true,
+            strict: true,
+            restorable_notes: None,
+            undo_history: vec![],
+            redo_history: vec![],
+            difficulty: None,
+            score: None,
+            seed: None,
+            mistakes: 0,
+            max_mistakes: None,
             headers: vec![],
             footers: HELP.iter().map(|str| String::from(*str)).collect(),
         };
-        game.new_grid(Difficulty::Easy);
+        if let Err(e) = game.new_grid(Shape::default(), false, false, Difficulty::Easy, None) {
+            game.set_message(format!("Error: {}", e));
+        }
         game
     }
 
@@ -53,6 +120,62 @@ impl Game {
         &self.start_time
     }
 
+    pub fn highlights(&self) -> &Vec<(usize, usize)> {
+        &self.highlights
+    }
+
+    pub fn view(&self) -> View {
+        self.view
+    }
+
+    pub fn set_view(&mut self, view: View) {
+        self.view = view;
+    }
+
+    pub fn auto_notes(&self) -> bool {
+        self.auto_notes
+    }
+
+    pub fn set_auto_notes(&mut self, auto_notes: bool) {
+        self.auto_notes = auto_notes;
+    }
+
+    /// Whether the values breaking a row, column or box are refused, instead of shown as conflicts.
+    pub fn strict(&self) -> bool {
+        self.strict
+    }
+
+    pub fn set_strict(&mut self, strict: bool) {
+        self.strict = strict;
+    }
+
+    /// The value cleared by the last move and how many notes it removed when it was placed.
+    pub fn restorable_notes(&self) -> Option<(u8, usize)> {
+        self.restorable_notes
+            .as_ref()
+            .map(|(val, peers)| (*val, peers.len()))
+    }
+
+    /// How many values placed in this game differ from the solution.
+    pub fn mistakes(&self) -> usize {
+        self.mistakes
+    }
+
+    /// The number of mistakes ending the game, if any.
+    pub fn max_mistakes(&self) -> Option<usize> {
+        self.max_mistakes
+    }
+
+    pub fn set_max_mistakes(&mut self, max_mistakes: Option<usize>) {
+        self.max_mistakes = max_mistakes;
+        self.update_headers();
+    }
+
+    /// Whether the game ended on too many mistakes.
+    pub fn is_lost(&self) -> bool {
+        self.max_mistakes.is_some_and(|max| self.mistakes >= max)
+    }
+
     pub fn headers(&self) -> &Vec<String> {
         &self.headers
     }
@@ -61,34 +184,377 @@ impl Game {
         &self.footers
     }
 
-    pub fn new_grid(&mut self, difficulty: Difficulty) {
-        self.headers = vec![
-            String::from("Sudoku"),
-            String::new(),
-            format!("Difficulty: {}", Into::<&str>::into(difficulty)),
-        ];
+    /// Start a generated sudoku, a killer sudoku if `killer` is set, with random regions instead
+    /// of boxes if `jigsaw` is set. The seed generates both.
+    pub fn new_grid(
+        &mut self,
+        shape: Shape,
+        killer: bool,
+        jigsaw: bool,
+        difficulty: Difficulty,
+        seed: Option<u64>,
+    ) -> Result<(), String> {
+        let seed = seed.unwrap_or_else(|| thread_rng().gen());
+        let shape = if jigsaw {
+            RegionGenerator::with_seed(shape, Some(seed)).generate()?
+        } else {
+            shape
+        };
+        let puzzle = if killer {
+            KillerGenerator::with_shape(shape, difficulty, Some(seed)).generate()?
+        } else {
+            BasicGenerator::with_shape(shape, difficulty, Some(seed)).generate()?
+        };
+        self.start(
+            puzzle.board().clone(),
+            puzzle.solution().clone(),
+            Some(puzzle.seed()),
+        );
+        // the rating only knows the difficulty of the 9x9 boards with givens, the generator knows
+        // the difficulty asked for
+        self.difficulty = Some(puzzle.difficulty());
+        self.update_headers();
+        Ok(())
+    }
+
+    /// Load a puzzle given in the one-line format, either directly or in a file.
+    pub fn load(&mut self, source: &str) -> Result<(), String> {
+        let board: Board = if Path::new(source).is_file() {
+            fs::read_to_string(source)
+                .map_err(|e| e.to_string())?
+                .parse()?
+        } else {
+            source.parse()?
+        };
+
+        // the parsing fixes the givens and leaves free the entries of the alternate format, which
+        // stay the player's entries: only the givens must have a unique solution
+        let mut givens = board.clone();
+        givens.reset();
+        let solution = Game::unique_solution(&givens)?;
+        self.start(givens, solution, None);
+        self.board = board;
+        Ok(())
+    }
+
+    /// The givens and the user's entries, each in the one-line format.
+    pub fn export(&self) -> (String, String) {
+        (self.board.to_string(), self.board.to_entries_string())
+    }
+
+    /// Write the board with its notes, the elapsed time and the difficulty to the file.
+    pub fn save<P: AsRef<Path>>(&self, path: P) -> Result<(), String> {
+        let saved = SavedGame {
+            board: self.board.clone(),
+            elapsed: self.start_time.elapsed(),
+            difficulty: self.difficulty,
+            score: self.score,
+            seed: self.seed,
+            mistakes: self.mistakes,
+            max_mistakes: self.max_mistakes,
+        };
+        fs::write(path, saved.to_string()).map_err(|e| e.to_string())
+    }
+
+    /// Continue the game saved in the file, with its timer where it stopped.
+    pub fn resume<P: AsRef<Path>>(&mut self, path: P) -> Result<(), String> {
+        let saved: SavedGame = fs::read_to_string(path)
+            .map_err(|e| e.to_string())?
+            .parse()?;
+
+        let mut givens = saved.board.clone();
+        givens.reset();
+        self.solution = Game::unique_solution(&givens)?;
+        self.board = saved.board;
+        self.clear_history();
+        self.difficulty = saved.difficulty;
+        self.score = saved.score;
+        self.seed = saved.seed;
+        self.mistakes = saved.mistakes;
+        self.max_mistakes = saved.max_mistakes;
+        self.start_time = Instant::now()
+            .checked_sub(saved.elapsed)
+            .unwrap_or_else(Instant::now);
+        self.update_headers();
+        Ok(())
+    }
+
+    /// Save the unfinished game in the data directory, or forget it once solved.
+    pub fn autosave(&self) -> Result<(), String> {
+        let path = match save::autosave_path() {
+            Some(path) => path,
+            None => return Ok(()),
+        };
+
+        if self.board.is_solved() || self.is_lost() {
+            if path.is_file() {
+                fs::remove_file(&path).map_err(|e| e.to_string())?;
+            }
+            return Ok(());
+        }
+
+        if let Some(dir) = path.parent() {
+            fs::create_dir_all(dir).map_err(|e| e.to_string())?;
+        }
+        self.save(&path)
+    }
+
+    fn start(&mut self, board: Board, solution: Board, seed: Option<u64>) {
+        if Rating::is_rated(&board) {
+            let rating = Rating::new(&board);
+            self.difficulty = rating.difficulty();
+            self.score = Some(rating.score());
+        } else {
+            self.difficulty = None;
+            self.score = None;
+        }
+        self.seed = seed;
+        self.mistakes = 0;
+        self.board = board;
+        self.solution = solution;
+        self.clear_history();
         self.start_time = Instant::now();
-        self.board = BasicGenerator::new(difficulty).generate();
+        self.update_headers();
+    }
+
+    fn unique_solution(givens: &Board) -> Result<Board, String> {
+        // the exact cover only checks the sums of the cages once they are filled, far too late
+        // for a killer sudoku without givens
+        let solver: Box<dyn Solver> = if givens.cages().is_empty() {
+            Box::new(DlxSolver::new())
+        } else {
+            Box::new(PropagationSolver::new())
+        };
+        match solver.count_solutions(givens, 2) {
+            0 => Err(String::from("No solution")),
+            1 => {
+                let mut solution = givens.clone();
+                solver.solve(&mut solution);
+                Ok(solution)
+            }
+            _ => Err(String::from("Several solutions")),
+        }
+    }
+
+    fn update_headers(&mut self) {
+        let mut difficulty = format!(
+            "Difficulty: {}",
+            self.difficulty.map_or("Unrated", |d| d.into())
+        );
+        if let Some(score) = self.score {
+            difficulty = format!("{} ({:.1})", difficulty, score);
+        }
+        if let Some(max_mistakes) = self.max_mistakes {
+            difficulty = format!(
+                "{}    Mistakes: {}/{}",
+                difficulty, self.mistakes, max_mistakes
+            );
+        }
+        if let Some(seed) = self.seed {
+            difficulty = format!("{}    Seed: {}", difficulty, seed);
+        }
+
+        let shape = self.board.shape();
+        let killer = if self.board.cages().is_empty() {
+            ""
+        } else {
+            "Killer "
+        };
+        let jigsaw = if shape.regions().is_some() {
+            "Jigsaw "
+        } else {
+            ""
+        };
+        let diagonals = if shape.diagonals() { " X" } else { "" };
+        let title = format!("{}{}Sudoku{}", killer, jigsaw, diagonals);
+        self.headers = vec![title, String::new(), difficulty];
     }
 
     pub fn fill_cell(&mut self, row: usize, col: usize, val: u8) -> Result<(), String> {
-        self.board.set_value(row, col, val)
+        if self.is_lost() {
+            return Err(String::from("Game over"));
+        }
+        // the value is already there: nothing to undo, and no new mistake
+        if val != 0 && self.board.get_value(row, col) == Some(val) {
+            return Ok(());
+        }
+
+        let previous = self.board.clone();
+        self.board.set_auto_notes(self.auto_notes);
+        self.board.set_allow_conflicts(!self.strict);
+        self.board.set_value(row, col, val)?;
+
+        let removed = previous.get_removed_notes(row, col).to_vec();
+        let cleared = previous.get_value(row, col).filter(|_| val == 0);
+        self.record(previous);
+        if let Some(cleared) = cleared.filter(|_| !removed.is_empty()) {
+            self.restorable_notes = Some((cleared, removed));
+        }
+
+        if val != 0 && self.max_mistakes.is_some() && self.solution.get_value(row, col) != Some(val)
+        {
+            self.mistakes += 1;
+            self.update_headers();
+        }
+        Ok(())
+    }
+
+    /// Put back the notes removed by the value cleared by the last move, in the cells still empty.
+    pub fn restore_notes(&mut self) -> Result<(), String> {
+        let (val, peers) = self
+            .restorable_notes
+            .take()
+            .ok_or_else(|| String::from("No notes to restore"))?;
+
+        let previous = self.board.clone();
+        for (row, col) in peers {
+            if self.board.get_value(row, col).is_none() {
+                let notes = self.board.get_notes(row, col) | FreeNumberMask::from_value(val);
+                self.board.set_notes(row, col, notes)?;
+            }
+        }
+        self.record(previous);
+        Ok(())
+    }
+
+    pub fn toggle_note(&mut self, row: usize, col: usize, val: u8) -> Result<(), String> {
+        let previous = self.board.clone();
+        self.board.toggle_note(row, col, val)?;
+        self.record(previous);
+        Ok(())
+    }
+
+    /// Clear the notes of the cell, or of every cell.
+    pub fn clear_notes(&mut self, cell: Option<(usize, usize)>) -> Result<(), String> {
+        self.edit_notes(cell, |_, _, _| FreeNumberMask::none())
+    }
+
+    /// Note the values that can go in the empty cell, or in every empty cell.
+    pub fn fill_notes(&mut self, cell: Option<(usize, usize)>) -> Result<(), String> {
+        self.edit_notes(cell, |board, row, col| board.get_candidates(row, col))
+    }
+
+    fn edit_notes<F>(&mut self, cell: Option<(usize, usize)>, notes: F) -> Result<(), String>
+    where
+        F: Fn(&Board, usize, usize) -> FreeNumberMask,
+    {
+        let previous = self.board.clone();
+        match cell {
+            Some((row, col)) => self.board.set_notes(row, col, notes(&previous, row, col))?,
+            None => {
+                for (row, col) in previous.shape().positions() {
+                    if previous.get_value(row, col).is_none() {
+                        self.board.set_notes(row, col, notes(&previous, row, col))?;
+                    }
+                }
+            }
+        }
+        self.record(previous);
+        Ok(())
     }
 
     pub fn solve(&mut self) -> bool {
-        let solved = SimpleSolver::new().solve(&mut self.board);
+        let solved = PropagationSolver::new().solve(&mut self.board);
         if solved {
-            self.board.freeze();
+            self.end();
         }
         solved
     }
 
+    /// Go back up to `count` moves. Returns how many moves were undone.
+    pub fn undo(&mut self, count: usize) -> usize {
+        self.restorable_notes = None;
+        let mut undone = 0;
+        while undone < count {
+            match self.undo_history.pop() {
+                Some(board) => {
+                    self.redo_history
+                        .push(std::mem::replace(&mut self.board, board));
+                    undone += 1;
+                }
+                None => break,
+            }
+        }
+        undone
+    }
+
+    /// Replay up to `count` undone moves. Returns how many moves were redone.
+    pub fn redo(&mut self, count: usize) -> usize {
+        self.restorable_notes = None;
+        let mut redone = 0;
+        while redone < count {
+            match self.redo_history.pop() {
+                Some(board) => {
+                    self.undo_history
+                        .push(std::mem::replace(&mut self.board, board));
+                    redone += 1;
+                }
+                None => break,
+            }
+        }
+        redone
+    }
+
+    /// Remember the board before a move, which makes the undone moves unreachable.
+    fn record(&mut self, previous: Board) {
+        self.undo_history.push(previous);
+        self.redo_history.clear();
+        self.restorable_notes = None;
+    }
+
+    fn clear_history(&mut self) {
+        self.undo_history.clear();
+        self.redo_history.clear();
+        self.restorable_notes = None;
+    }
+
+    /// The free cells whose value differs from the solution.
+    pub fn wrong_entries(&self) -> Vec<(usize, usize)> {
+        self.board
+            .shape()
+            .positions()
+            .filter(|(row, col)| match self.board.get_value(*row, *col) {
+                Some(val) => self.solution.get_value(*row, *col) != Some(val),
+                None => false,
+            })
+            .collect()
+    }
+
+    /// Highlight the wrong entries, without telling the right values. Returns how many there are.
+    pub fn check(&mut self) -> usize {
+        self.highlights = self.wrong_entries();
+        self.highlights.len()
+    }
+
+    /// Explain how the next value can be deduced and highlight the cells involved.
+    pub fn hint(&mut self) -> Option<String> {
+        if self.board.has_conflicts() {
+            return Some(String::from("Some values conflict, fix them first."));
+        }
+
+        let steps = LogicalSolver::new().steps_to_next_value(&self.board);
+        let (step, previous) = steps.split_last()?;
+
+        self.highlights = step.cells.clone();
+        let mut hint = step.to_string();
+        if !previous.is_empty() {
+            let mut techniques: Vec<&str> =
+                previous.iter().map(|step| step.technique.into()).collect();
+            techniques.dedup();
+            hint = format!("{} (after {})", hint, techniques.join(", "));
+        }
+        Some(hint)
+    }
+
     pub fn end(&mut self) {
-        self.board.freeze()
+        self.board.freeze();
+        self.clear_history();
     }
 
     pub fn reset(&mut self) {
+        let previous = self.board.clone();
         self.board.reset();
+        self.record(previous);
         self.start_time = Instant::now();
     }
 
@@ -99,14 +565,152 @@ impl Game {
     pub fn play(&mut self) {
         let mut console = ConsoleRender::new();
 
+        let unfinished = save::autosave_path().filter(|path| path.is_file());
+        if unfinished.is_some() {
+            self.set_message(String::from("Continue your unfinished game? [y/n]"));
+        }
+
         console.render(self);
         console.render(self); // workaround to clean the screen
 
+        if let Some(path) = unfinished {
+            if input::read_confirmation() {
+                match self.resume(&path) {
+                    Ok(_) => self.set_message(String::from("Game resumed.")),
+                    Err(e) => {
+                        self.set_message(format!("Error: {} (resume: {})", e, path.display()))
+                    }
+                }
+            } else {
+                self.set_message(String::from("Welcome"));
+            }
+            console.render(self);
+        }
+
         while !self.quit {
             let command = input::read_input_command();
+            self.highlights.clear();
             command(self);
 
+            // saved before rendering, so that nothing is lost if the terminal goes away
+            if let Err(e) = self.autosave() {
+                self.set_message(format!("Error: {} (autosave)", e));
+            }
             console.render(self);
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+
+    use crate::board::Board;
+    use crate::game::Game;
+
+    const PUZZLE: &str =
+        "530070000600195000098000060800060003400803001700020006060000280000419005000080079";
+
+    #[test]
+    fn test_load_entries() {
+        let mut board = PUZZLE.parse::<Board>().unwrap();
+        board.set_value(0, 2, 4).unwrap();
+        board.set_value(0, 3, 2).unwrap();
+        let mut game = Game::new();
+        game.load(&format!("{:#}", board)).unwrap();
+
+        assert!(game.board().is_fixed_value(0, 0));
+        assert_eq!(Some(4), game.board().get_value(0, 2));
+        assert!(!game.board().is_fixed_value(0, 2));
+        assert_eq!(1, game.check());
+        assert_eq!(&vec![(0, 3)], game.highlights());
+    }
+
+    #[test]
+    fn test_undo_redo() {
+        let mut game = Game::new();
+        game.load(PUZZLE).unwrap();
+        game.fill_cell(0, 2, 4).unwrap();
+        game.fill_cell(0, 3, 6).unwrap();
+        assert!(game.fill_cell(0, 4, 5).is_err());
+        game.reset();
+
+        assert_eq!(1, game.undo(1));
+        assert_eq!(Some(6), game.board().get_value(0, 3));
+        assert_eq!(2, game.undo(5));
+        assert_eq!(
+            PUZZLE.parse::<Board>().unwrap().to_string(),
+            game.board().to_string()
+        );
+        assert_eq!(None, game.board().get_value(0, 2));
+
+        assert_eq!(1, game.redo(1));
+        assert_eq!(Some(4), game.board().get_value(0, 2));
+        game.fill_cell(8, 0, 3).unwrap();
+        assert_eq!(0, game.redo(1));
+        assert_eq!(2, game.undo(2));
+        assert_eq!(0, game.undo(1));
+    }
+
+    #[test]
+    fn test_check() {
+        let mut game = Game::new();
+        game.load(PUZZLE).unwrap();
+        assert_eq!(0, game.check());
+
+        game.fill_cell(0, 2, 4).unwrap();
+        game.fill_cell(0, 3, 2).unwrap();
+        assert_eq!(1, game.check());
+        assert_eq!(&vec![(0, 3)], game.highlights());
+    }
+
+    #[test]
+    fn test_mistakes() {
+        let mut game = Game::new();
+        game.load(PUZZLE).unwrap();
+        game.set_max_mistakes(Some(2));
+        game.set_strict(false);
+        game.fill_cell(0, 2, 4).unwrap();
+        game.fill_cell(0, 3, 2).unwrap();
+        assert_eq!(1, game.mistakes());
+        assert!(!game.is_lost());
+        assert!(game.headers()[2].contains("Mistakes: 1/2"));
+
+        game.fill_cell(0, 3, 1).unwrap();
+        assert!(game.is_lost());
+        assert!(game.fill_cell(0, 3, 6).is_err());
+    }
+
+    #[test]
+    fn test_mistakes_same_value() {
+        let mut game = Game::new();
+        game.load(PUZZLE).unwrap();
+        game.set_max_mistakes(Some(3));
+        game.set_strict(false);
+        (0..3).for_each(|_| game.fill_cell(0, 2, 1).unwrap());
+        assert_eq!(1, game.mistakes());
+        assert!(!game.is_lost());
+
+        assert_eq!(1, game.undo(3));
+        assert_eq!(None, game.board().get_value(0, 2));
+    }
+
+    #[test]
+    fn test_restore_notes() {
+        let mut game = Game::new();
+        game.load(PUZZLE).unwrap();
+        game.toggle_note(0, 2, 1).unwrap();
+        game.toggle_note(0, 2, 2).unwrap();
+        game.fill_cell(1, 1, 2).unwrap();
+        assert_eq!(vec![1], game.board().get_notes(0, 2).values());
+
+        game.fill_cell(1, 1, 0).unwrap();
+        assert_eq!(Some((2, 1)), game.restorable_notes());
+        game.restore_notes().unwrap();
+        assert_eq!(vec![1, 2], game.board().get_notes(0, 2).values());
+        assert!(game.restore_notes().is_err());
+
+        game.set_auto_notes(false);
+        game.fill_cell(1, 1, 2).unwrap();
+        assert_eq!(vec![1, 2], game.board().get_notes(0, 2).values());
+    }
+}
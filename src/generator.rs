@@ -1,15 +1,58 @@
+use core::cmp;
+use std::str::FromStr;
+
 use rand::prelude::SliceRandom;
-use rand::thread_rng;
+use rand::rngs::StdRng;
+use rand::{thread_rng, Rng, SeedableRng};
 
-use crate::board::{Board, BOARD_SIZE};
-use crate::solver::{SimpleSolver, Solver};
+use crate::board::{Board, Cage, Shape, BOARD_SIZE};
+use crate::rating::Rating;
+use crate::solver::{DlxSolver, PropagationSolver, Solver};
 
-const EASY: u8 = 38;
-const MEDIUM: u8 = 30;
+// Number of cells left filled on the 9x9 boards. The difficulty itself is checked by rating the
+// grid, these only make the expected rating more likely.
+const EASY: u8 = 36;
+const MEDIUM: u8 = 27;
 const HARD: u8 = 25;
-const EXPERT: u8 = 23;
+const EXPERT: u8 = 24;
+
+// Share of the cells left filled on the other sizes, which are not rated. Below half of the cells,
+// checking that a 25x25 board has a unique solution becomes far too slow.
+const EASY_SHARE: f32 = 0.6;
+const MEDIUM_SHARE: f32 = 0.56;
+const HARD_SHARE: f32 = 0.53;
+const EXPERT_SHARE: f32 = 0.5;
+
+// Largest cages of killer sudoku: the larger the cages, the more combinations of values they have.
+const EASY_CAGE: usize = 3;
+const MEDIUM_CAGE: usize = 4;
+const HARD_CAGE: usize = 5;
+const EXPERT_CAGE: usize = 6;
+
+// Branches the solver may take to check a merge of two cages. Beyond, the cages are kept apart: the
+// check would take too long, and so would solving the puzzle.
+const MAX_BRANCHES: usize = 100;
+
+// Swaps of cells between neighbor regions tried per cell when generating the regions of jigsaw
+// sudoku, enough to leave little of the boxes the regions start from.
+const REGION_SWAPS: usize = 10;
+
+// Branches per cell the solver may take to fill an empty board with a random grid. Most grids
+// need far fewer, but the order given by some seeds gets lost for minutes, on 25x25 boards and
+// more often on irregular regions: another seed is tried instead.
+const FILL_BRANCHES: usize = 10;
+
+const MAX_ATTEMPTS: usize = 100;
+
+/// Size of the largest killer sudoku: beyond, checking each merge of cages takes seconds on 12x12
+/// boards and far longer on 16x16 boards.
+pub const MAX_KILLER_SIZE: usize = BOARD_SIZE;
 
-#[derive(Copy, Clone)]
+/// Size of the largest jigsaw sudoku: beyond, the regions or the grid filling them are often not
+/// found within the attempts.
+pub const MAX_JIGSAW_SIZE: usize = 12;
+
+#[derive(Copy, Clone, Debug, PartialEq)]
 pub enum Difficulty {
     Easy,
     Medium,
@@ -17,6 +60,28 @@ pub enum Difficulty {
     Expert,
 }
 
+impl Difficulty {
+    pub const ALL: [Difficulty; 4] = [
+        Difficulty::Easy,
+        Difficulty::Medium,
+        Difficulty::Hard,
+        Difficulty::Expert,
+    ];
+}
+
+impl FromStr for Difficulty {
+    type Err = String;
+
+    /// Parse the name of a difficulty, ignoring case.
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Difficulty::ALL
+            .iter()
+            .find(|difficulty| Into::<&str>::into(**difficulty).eq_ignore_ascii_case(s))
+            .copied()
+            .ok_or_else(|| format!("Unknown difficulty '{}'", s))
+    }
+}
+
 impl Into<&'static str> for Difficulty {
     fn into(self) -> &'static str {
         match self {
@@ -28,45 +93,508 @@ impl Into<&'static str> for Difficulty {
     }
 }
 
+/// A generated board, with the seed generating it again.
+pub struct Puzzle {
+    board: Board,
+    solution: Board,
+    difficulty: Difficulty,
+    seed: u64,
+}
+
+impl Puzzle {
+    pub fn board(&self) -> &Board {
+        &self.board
+    }
+
+    /// The unique solution of the board, the grid the clues were removed from.
+    pub fn solution(&self) -> &Board {
+        &self.solution
+    }
+
+    pub fn difficulty(&self) -> Difficulty {
+        self.difficulty
+    }
+
+    pub fn seed(&self) -> u64 {
+        self.seed
+    }
+}
+
 pub trait Generator {
-    fn generate(&self) -> Board;
+    fn generate(&self) -> Result<Puzzle, String>;
 }
 
 pub struct BasicGenerator {
-    nb_filled_cell: u8,
+    shape: Shape,
+    difficulty: Difficulty,
+    nb_filled_cell: usize,
+    seed: Option<u64>,
 }
 
 impl Generator for BasicGenerator {
-    fn generate(&self) -> Board {
-        let mut board = Board::new();
-        SimpleSolver::new().solve(&mut board); // always solvable
+    fn generate(&self) -> Result<Puzzle, String> {
+        let seed = self.seed.unwrap_or_else(|| thread_rng().gen());
+        let mut rng = StdRng::seed_from_u64(seed);
 
-        let total_cells = BOARD_SIZE * BOARD_SIZE;
-        let mut cells: Vec<usize> = (0..total_cells).collect();
-        cells.shuffle(&mut thread_rng());
+        let mut min_filled_cell = self.shape.nb_cells();
+        let mut nb_rated = 0;
 
-        cells
-            .iter()
-            .take(total_cells - self.nb_filled_cell as usize)
-            .for_each(|pos| {
-                board
-                    .clear_value(pos / BOARD_SIZE, pos % BOARD_SIZE)
-                    .unwrap()
-            });
+        for _ in 0..MAX_ATTEMPTS {
+            let mut board = random_grid(&self.shape, &mut rng)?;
+            let mut solution = board.clone();
+
+            let nb_filled_cell = self.remove_cells(&mut board, &mut rng);
+            min_filled_cell = cmp::min(min_filled_cell, nb_filled_cell);
+            if nb_filled_cell != self.nb_filled_cell {
+                continue;
+            }
 
-        board.freeze();
-        board
+            nb_rated += 1;
+            let (min, max) = Rating::band(self.difficulty);
+            if !Rating::is_rated(&board) || (min..max).contains(&Rating::new(&board).score()) {
+                board.freeze();
+                solution.freeze();
+                return Ok(Puzzle {
+                    board,
+                    solution,
+                    difficulty: self.difficulty,
+                    seed,
+                });
+            }
+        }
+
+        if nb_rated == 0 {
+            return Err(format!(
+                "Unable to generate a sudoku with {} filled cells (best: {}, seed: {})",
+                self.nb_filled_cell, min_filled_cell, seed
+            ));
+        }
+        Err(format!(
+            "Unable to generate a sudoku rated {} in {} attempts (seed: {})",
+            Into::<&str>::into(self.difficulty),
+            MAX_ATTEMPTS,
+            seed
+        ))
     }
 }
 
 impl BasicGenerator {
     pub fn new(difficulty: Difficulty) -> BasicGenerator {
-        let nb_filled_cell = match difficulty {
-            Difficulty::Easy => EASY,
-            Difficulty::Medium => MEDIUM,
-            Difficulty::Hard => HARD,
-            Difficulty::Expert => EXPERT,
+        BasicGenerator::with_seed(difficulty, None)
+    }
+
+    /// A generator always giving the same puzzle for the same seed, or a random one for `None`.
+    pub fn with_seed(difficulty: Difficulty, seed: Option<u64>) -> BasicGenerator {
+        BasicGenerator::with_shape(Shape::default(), difficulty, seed)
+    }
+
+    /// A generator of boards of the given shape.
+    pub fn with_shape(shape: Shape, difficulty: Difficulty, seed: Option<u64>) -> BasicGenerator {
+        let nb_filled_cell = if shape.size() == BOARD_SIZE {
+            match difficulty {
+                Difficulty::Easy => EASY,
+                Difficulty::Medium => MEDIUM,
+                Difficulty::Hard => HARD,
+                Difficulty::Expert => EXPERT,
+            }
+            .into()
+        } else {
+            let share = match difficulty {
+                Difficulty::Easy => EASY_SHARE,
+                Difficulty::Medium => MEDIUM_SHARE,
+                Difficulty::Hard => HARD_SHARE,
+                Difficulty::Expert => EXPERT_SHARE,
+            };
+            (shape.nb_cells() as f32 * share).round() as usize
+        };
+        BasicGenerator {
+            shape,
+            difficulty,
+            nb_filled_cell,
+            seed,
+        }
+    }
+
+    /// Remove the cells one at a time in random order, putting back any value whose removal
+    /// gives the board more than one solution. Returns the number of cells still filled.
+    fn remove_cells(&self, board: &mut Board, rng: &mut StdRng) -> usize {
+        let solver = DlxSolver::new();
+
+        let total_cells = self.shape.nb_cells();
+        let mut cells: Vec<usize> = (0..total_cells).collect();
+        cells.shuffle(rng);
+
+        let mut nb_filled_cell = total_cells;
+        for pos in cells {
+            if nb_filled_cell == self.nb_filled_cell {
+                break;
+            }
+
+            let (row, col) = (pos / self.shape.size(), pos % self.shape.size());
+            let val = board.get_value(row, col).unwrap();
+            board.clear_value(row, col).unwrap();
+
+            if solver.has_unique_solution(board) {
+                nb_filled_cell -= 1;
+            } else {
+                board.set_value(row, col, val).unwrap();
+            }
+        }
+
+        nb_filled_cell
+    }
+}
+
+/// A random solved grid of the shape.
+fn random_grid(shape: &Shape, rng: &mut StdRng) -> Result<Board, String> {
+    for _ in 0..MAX_ATTEMPTS {
+        let mut board = Board::with_shape(shape.clone());
+        let solver = PropagationSolver::with_seed(rng.gen());
+        if solver.try_solve(&mut board, FILL_BRANCHES * shape.nb_cells()) == Some(true) {
+            return Ok(board);
+        }
+    }
+    Err(format!(
+        "Unable to fill a {} grid in {} attempts",
+        shape, MAX_ATTEMPTS
+    ))
+}
+
+/// Generates killer sudoku, with no givens: the cells of a solved grid start in cages of their own,
+/// and neighbor cages are merged as long as the grid stays the only solution.
+pub struct KillerGenerator {
+    shape: Shape,
+    difficulty: Difficulty,
+    max_cage_size: usize,
+    seed: Option<u64>,
+}
+
+impl Generator for KillerGenerator {
+    fn generate(&self) -> Result<Puzzle, String> {
+        let seed = self.seed.unwrap_or_else(|| thread_rng().gen());
+        let mut rng = StdRng::seed_from_u64(seed);
+
+        let mut solution = random_grid(&self.shape, &mut rng)?;
+        let cages = self.merge_cages(&solution, &mut rng);
+
+        let mut board = Board::with_shape(self.shape.clone());
+        board.set_cages(cages.clone())?;
+        solution.set_cages(cages)?;
+        solution.freeze();
+        Ok(Puzzle {
+            board,
+            solution,
+            difficulty: self.difficulty,
+            seed,
+        })
+    }
+}
+
+impl KillerGenerator {
+    pub fn new(difficulty: Difficulty) -> KillerGenerator {
+        KillerGenerator::with_seed(difficulty, None)
+    }
+
+    /// A generator always giving the same puzzle for the same seed, or a random one for `None`.
+    pub fn with_seed(difficulty: Difficulty, seed: Option<u64>) -> KillerGenerator {
+        KillerGenerator::with_shape(Shape::default(), difficulty, seed)
+    }
+
+    /// A generator of boards of the given shape.
+    pub fn with_shape(shape: Shape, difficulty: Difficulty, seed: Option<u64>) -> KillerGenerator {
+        let max_cage_size = match difficulty {
+            Difficulty::Easy => EASY_CAGE,
+            Difficulty::Medium => MEDIUM_CAGE,
+            Difficulty::Hard => HARD_CAGE,
+            Difficulty::Expert => EXPERT_CAGE,
+        };
+        KillerGenerator {
+            shape,
+            difficulty,
+            max_cage_size,
+            seed,
+        }
+    }
+
+    /// Visit the cells in random order, merging the cage of each cell with the cage of a neighbor
+    /// when the merged cage has no repeated value, is not too large, and leaves the solved grid
+    /// the only solution of the cages.
+    fn merge_cages(&self, solution: &Board, rng: &mut StdRng) -> Vec<Cage> {
+        let solver = PropagationSolver::new();
+        let size = self.shape.size();
+        let mut groups: Vec<Vec<(usize, usize)>> =
+            self.shape.positions().map(|cell| vec![cell]).collect();
+        let mut group_index: Vec<usize> = (0..groups.len()).collect();
+
+        let mut cells: Vec<(usize, usize)> = self.shape.positions().collect();
+        cells.shuffle(rng);
+        for (row, col) in cells {
+            let mut neighbors = vec![];
+            if row > 0 {
+                neighbors.push((row - 1, col));
+            }
+            if row + 1 < size {
+                neighbors.push((row + 1, col));
+            }
+            if col > 0 {
+                neighbors.push((row, col - 1));
+            }
+            if col + 1 < size {
+                neighbors.push((row, col + 1));
+            }
+            neighbors.shuffle(rng);
+
+            for (r, c) in neighbors {
+                let (group, other) = (group_index[row * size + col], group_index[r * size + c]);
+                let value = |&(row, col): &(usize, usize)| solution.get_value(row, col);
+                if group == other
+                    || groups[group].len() + groups[other].len() > self.max_cage_size
+                    || groups[group]
+                        .iter()
+                        .any(|cell| groups[other].iter().any(|o| value(o) == value(cell)))
+                {
+                    continue;
+                }
+
+                let mut merged = groups.clone();
+                let moved = std::mem::take(&mut merged[other]);
+                merged[group].extend(moved);
+                let mut board = Board::with_shape(self.shape.clone());
+                board.set_cages(Self::to_cages(&merged, solution)).unwrap();
+                if solver.try_count_solutions(&board, 2, MAX_BRANCHES) == Some(1) {
+                    for &(r, c) in merged[group].iter() {
+                        group_index[r * size + c] = group;
+                    }
+                    groups = merged;
+                    break;
+                }
+            }
+        }
+
+        Self::to_cages(&groups, solution)
+    }
+
+    /// The cages of the groups of cells, summing their values in the solution. The cells of each
+    /// cage are sorted, so that the first one is its top left cell.
+    fn to_cages(groups: &[Vec<(usize, usize)>], solution: &Board) -> Vec<Cage> {
+        groups
+            .iter()
+            .filter(|cells| !cells.is_empty())
+            .map(|cells| {
+                let mut cells = cells.clone();
+                cells.sort_unstable();
+                let sum = cells
+                    .iter()
+                    .map(|&(row, col)| solution.get_value(row, col).unwrap() as u32)
+                    .sum();
+                Cage::new(cells, sum)
+            })
+            .collect()
+    }
+}
+
+/// Generates the region maps of jigsaw sudoku. Starting from the boxes, a cell and a cell of a
+/// neighbor region swap their regions as long as both regions stay connected, so that every map
+/// is valid. A map is only kept if the solver quickly fills it: some maps have no grid at all.
+pub struct RegionGenerator {
+    shape: Shape,
+    seed: Option<u64>,
+}
+
+impl RegionGenerator {
+    pub fn new(shape: Shape) -> RegionGenerator {
+        RegionGenerator::with_seed(shape, None)
+    }
+
+    /// A generator always giving the same regions for the same seed, or random ones for `None`.
+    pub fn with_seed(shape: Shape, seed: Option<u64>) -> RegionGenerator {
+        RegionGenerator { shape, seed }
+    }
+
+    /// The shape with random regions instead of its boxes.
+    pub fn generate(&self) -> Result<Shape, String> {
+        let seed = self.seed.unwrap_or_else(|| thread_rng().gen());
+        let mut rng = StdRng::seed_from_u64(seed);
+
+        let mut shape = self.shape.clone();
+        shape.set_regions(None).unwrap();
+        let boxes: Vec<usize> = shape
+            .positions()
+            .map(|(row, col)| shape.box_index(row, col))
+            .collect();
+        let solver = PropagationSolver::new();
+        for _ in 0..MAX_ATTEMPTS {
+            let mut regions = boxes.clone();
+            for _ in 0..REGION_SWAPS * shape.nb_cells() {
+                self.swap_cells(&mut regions, &mut rng);
+            }
+
+            let mut jigsaw = shape.clone();
+            jigsaw.set_regions(Some(regions)).unwrap(); // the swaps keep the regions valid
+            let board = Board::with_shape(jigsaw.clone());
+            if solver.try_count_solutions(&board, 1, FILL_BRANCHES * jigsaw.nb_cells()) == Some(1) {
+                return Ok(jigsaw);
+            }
+        }
+        Err(format!(
+            "Unable to generate jigsaw regions in {} attempts (seed: {})",
+            MAX_ATTEMPTS, seed
+        ))
+    }
+
+    /// Move a random cell to the region of one of its neighbors, and a cell of that region next to
+    /// the first region the other way, unless either region gets split.
+    fn swap_cells(&self, regions: &mut [usize], rng: &mut StdRng) {
+        let size = self.shape.size();
+        let neighbors = |cell: usize| {
+            self.shape
+                .neighbors(cell / size, cell % size)
+                .map(move |(row, col)| row * size + col)
+        };
+
+        let cell = rng.gen_range(0..regions.len());
+        let from = regions[cell];
+        let others: Vec<usize> = neighbors(cell).filter(|n| regions[*n] != from).collect();
+        let to = match others.choose(rng) {
+            Some(other) => regions[*other],
+            None => return,
         };
-        BasicGenerator { nb_filled_cell }
+        let borders: Vec<usize> = (0..regions.len())
+            .filter(|other| regions[*other] == to && neighbors(*other).any(|n| regions[n] == from))
+            .collect();
+        let other = *borders.choose(rng).unwrap(); // at least the neighbor of the cell
+
+        regions.swap(cell, other);
+        if !self.shape.is_connected(regions, from) || !self.shape.is_connected(regions, to) {
+            regions.swap(cell, other);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+
+    use crate::board::{Shape, BOARD_SIZE};
+    use crate::generator::{
+        BasicGenerator, Difficulty, Generator, KillerGenerator, RegionGenerator,
+    };
+    use crate::rating::Rating;
+    use crate::solver::{DlxSolver, PropagationSolver, SimpleSolver, Solver};
+
+    #[test]
+    fn test_generate_unique_solution() {
+        let puzzle = BasicGenerator::new(Difficulty::Medium).generate().unwrap();
+        assert!(SimpleSolver::new().has_unique_solution(puzzle.board()));
+
+        let mut board = puzzle.board().clone();
+        assert!(SimpleSolver::new().solve(&mut board));
+        assert!(puzzle.solution().is_solved());
+        assert_eq!(
+            format!("{:#}", board).replace('+', ""),
+            puzzle.solution().to_string()
+        );
+    }
+
+    #[test]
+    fn test_generate_rating() {
+        let puzzle = BasicGenerator::new(Difficulty::Easy).generate().unwrap();
+        assert!(Rating::new(puzzle.board()).score() < Rating::band(Difficulty::Medium).0);
+    }
+
+    #[test]
+    fn test_generate_with_seed() {
+        let first = BasicGenerator::with_seed(Difficulty::Easy, Some(7))
+            .generate()
+            .unwrap();
+        let second = BasicGenerator::with_seed(Difficulty::Easy, Some(7))
+            .generate()
+            .unwrap();
+        assert_eq!(7, first.seed());
+        (0..BOARD_SIZE * BOARD_SIZE).for_each(|i| {
+            let (row, col) = (i / BOARD_SIZE, i % BOARD_SIZE);
+            assert_eq!(
+                first.board().get_value(row, col),
+                second.board().get_value(row, col)
+            );
+        });
+    }
+
+    #[test]
+    fn test_generate_other_sizes() {
+        for shape in [Shape::new(2), Shape::with_boxes(2, 3), Shape::new(4)].iter() {
+            let puzzle = BasicGenerator::with_shape(shape.clone(), Difficulty::Hard, Some(3))
+                .generate()
+                .unwrap();
+            assert_eq!(shape, puzzle.board().shape());
+            assert!(puzzle.solution().is_solved());
+            assert!(DlxSolver::new().has_unique_solution(puzzle.board()));
+        }
+    }
+
+    #[test]
+    fn test_generate_diagonals() {
+        let mut shape = Shape::default();
+        shape.set_diagonals(true);
+        let puzzle = BasicGenerator::with_shape(shape, Difficulty::Medium, Some(5))
+            .generate()
+            .unwrap();
+        assert!(puzzle.board().shape().diagonals());
+        assert!(DlxSolver::new().has_unique_solution(puzzle.board()));
+
+        let solution = puzzle.solution();
+        let mut values: Vec<u8> = (0..BOARD_SIZE)
+            .map(|i| solution.get_value(i, BOARD_SIZE - 1 - i).unwrap())
+            .collect();
+        values.sort_unstable();
+        assert_eq!((1..=BOARD_SIZE as u8).collect::<Vec<u8>>(), values);
+    }
+
+    #[test]
+    fn test_generate_killer() {
+        for shape in [Shape::new(2), Shape::default()].iter() {
+            let puzzle = KillerGenerator::with_shape(shape.clone(), Difficulty::Easy, Some(3))
+                .generate()
+                .unwrap();
+            let board = puzzle.board();
+            assert!(board
+                .shape()
+                .positions()
+                .all(|(row, col)| board.get_value(row, col).is_none()
+                    && board.get_cage(row, col).is_some()));
+            assert!(board.cages().iter().any(|cage| cage.cells().len() > 1));
+            assert!(PropagationSolver::new().has_unique_solution(board));
+            assert!(puzzle.solution().is_solved());
+        }
+    }
+
+    #[test]
+    fn test_generate_regions() {
+        for shape in [Shape::new(2), Shape::with_boxes(2, 3), Shape::default()].iter() {
+            let jigsaw = RegionGenerator::with_seed(shape.clone(), Some(4))
+                .generate()
+                .unwrap();
+            let mut valid = shape.clone();
+            assert!(valid
+                .set_regions(jigsaw.regions().map(<[usize]>::to_vec))
+                .is_ok());
+            assert_ne!(shape, &jigsaw);
+        }
+        assert_eq!(
+            RegionGenerator::with_seed(Shape::default(), Some(4)).generate(),
+            RegionGenerator::with_seed(Shape::default(), Some(4)).generate(),
+        );
+    }
+
+    #[test]
+    fn test_generate_jigsaw() {
+        let shape = RegionGenerator::with_seed(Shape::default(), Some(6))
+            .generate()
+            .unwrap();
+        let puzzle = BasicGenerator::with_shape(shape.clone(), Difficulty::Medium, Some(6))
+            .generate()
+            .unwrap();
+        assert_eq!(&shape, puzzle.board().shape());
+        assert!(DlxSolver::new().has_unique_solution(puzzle.board()));
+        assert!(puzzle.solution().is_solved());
     }
 }
@@ -1,18 +1,31 @@
 use std::collections::HashMap;
 use std::io;
 
-use crate::board::BOARD_SIZE;
-use crate::game::Game;
-use crate::generator::Difficulty;
+use crate::board::{char_to_value, value_to_char, Shape, MAX_BOARD_SIZE};
+use crate::game::{Game, View, SAVE_PATH};
+use crate::generator::{Difficulty, MAX_JIGSAW_SIZE, MAX_KILLER_SIZE};
 
 pub type InputCommand = Box<dyn FnOnce(&mut Game) -> ()>;
 pub type ParseCommand = fn(Vec<&str>) -> InputCommand;
+type Setting = (fn(&Game) -> bool, fn(&mut Game, bool));
 
 lazy_static! {
     static ref COMMANDS: HashMap<&'static str, ParseCommand> = {
         let mut m = HashMap::<&'static str, ParseCommand>::new();
         m.insert("new", cmd_new);
         m.insert("clear", cmd_clear_cell_value);
+        m.insert("load", cmd_load);
+        m.insert("export", cmd_export);
+        m.insert("save", cmd_save);
+        m.insert("resume", cmd_resume);
+        m.insert("note", cmd_note);
+        m.insert("view", cmd_view);
+        m.insert("set", cmd_set);
+        m.insert("undo", cmd_undo);
+        m.insert("redo", cmd_redo);
+        m.insert("check", cmd_check);
+        m.insert("lives", cmd_lives);
+        m.insert("hint", cmd_hint);
         m.insert("solve", cmd_solve);
         m.insert("reset", cmd_reset);
         m.insert("quit", cmd_quit);
@@ -36,6 +49,15 @@ pub fn read_input_command() -> InputCommand {
     }
 }
 
+/// Read a yes or no answer, anything else than yes meaning no.
+pub fn read_confirmation() -> bool {
+    let mut line = String::new();
+    if io::stdin().read_line(&mut line).is_err() {
+        return false;
+    }
+    matches!(line.trim().to_lowercase().as_str(), "y" | "yes")
+}
+
 fn cmd_error(args: Vec<&'static str>) -> InputCommand {
     assert_eq!(args.len(), 1);
     let message = args[0];
@@ -44,34 +66,52 @@ fn cmd_error(args: Vec<&'static str>) -> InputCommand {
     })
 }
 
-fn cmd_new(args: Vec<&str>) -> InputCommand {
-    let difficulty = args
-        .get(1)
-        .map(|s| s.to_lowercase())
-        .map(|s| match s.as_str() {
-            "easy" => Some(Difficulty::Easy),
-            "medium" => Some(Difficulty::Medium),
-            "hard" => Some(Difficulty::Hard),
-            "expert" => Some(Difficulty::Expert),
-            _ => None,
-        })
-        .flatten();
-
-    if let Some(d) = difficulty {
-        Box::new(move |game| {
-            game.new_grid(d);
-            game.set_message(String::new());
-        })
+fn cmd_new(mut args: Vec<&str>) -> InputCommand {
+    // the variants and the size come before the difficulty, in any order
+    let (mut killer, mut diagonals, mut jigsaw, mut size) = (false, false, false, None);
+    while let Some(arg) = args.get(1) {
+        match arg.to_ascii_lowercase().as_str() {
+            "killer" => killer = true,
+            "x" => diagonals = true,
+            "jigsaw" => jigsaw = true,
+            _ => match arg.parse::<Shape>() {
+                Ok(shape) if size.is_none() => size = Some(shape),
+                _ => break,
+            },
+        }
+        args.remove(1);
+    }
+    let mut shape = size.unwrap_or_default();
+    shape.set_diagonals(diagonals);
+    let difficulty = args.get(1).and_then(|s| s.parse::<Difficulty>().ok());
+    let seed = match args.get(2) {
+        Some(s) => s.parse::<u64>().map(Some),
+        None => Ok(None),
+    };
+
+    if killer && shape.size() > MAX_KILLER_SIZE {
+        cmd_error(vec!["Killer sudoku go up to 9x9"])
+    } else if jigsaw && shape.size() > MAX_JIGSAW_SIZE {
+        cmd_error(vec!["Jigsaw sudoku go up to 12x12"])
+    } else if let (Some(d), Ok(seed)) = (difficulty, seed) {
+        Box::new(
+            move |game| match game.new_grid(shape, killer, jigsaw, d, seed) {
+                Ok(_) => game.set_message(String::new()),
+                Err(e) => game.set_message(format!("Error: {}", e)),
+            },
+        )
     } else {
-        cmd_error(vec!["Usage: new [easy|medium|hard|expert]"])
+        cmd_error(vec![
+            "Usage: new [killer] [x] [jigsaw] [4x4|6x6|8x8|9x9|12x12|16x16|25x25] [easy|medium|hard|expert] [seed]",
+        ])
     }
 }
 
 fn cmd_write_cell_value(args: Vec<&str>) -> InputCommand {
     if args.len() == 3 {
-        if let Ok(row) = read_one_digit(args[0]) {
-            if let Ok(col) = read_one_digit(args[1]) {
-                if let Ok(val) = read_one_digit(args[2]) {
+        if let Ok(row) = read_number(args[0]) {
+            if let Ok(col) = read_number(args[1]) {
+                if let Ok(val) = read_value(args[2]) {
                     return Box::new(move |game| {
                         match game.fill_cell((row - 1) as usize, (col - 1) as usize, val) {
                             Ok(_) => {
@@ -83,13 +123,26 @@ fn cmd_write_cell_value(args: Vec<&str>) -> InputCommand {
                                         seconds % 60
                                     ));
                                     game.end();
+                                } else if game.is_lost() {
+                                    game.set_message(format!(
+                                        "Game over: {} mistakes. 'solve' shows the solution.",
+                                        game.mistakes()
+                                    ));
                                 } else {
-                                    game.set_message(format!("[{},{}] = {} done", row, col, val))
+                                    game.set_message(format!(
+                                        "[{},{}] = {} done",
+                                        row,
+                                        col,
+                                        value_to_char(val)
+                                    ))
                                 }
                             }
                             Err(e) => game.set_message(format!(
                                 "Error: {} (input: [{},{}] = {})",
-                                e, row, col, val
+                                e,
+                                row,
+                                col,
+                                value_to_char(val)
                             )),
                         };
                     });
@@ -97,16 +150,25 @@ fn cmd_write_cell_value(args: Vec<&str>) -> InputCommand {
             }
         }
     }
-    cmd_error(vec!["Usage: <row:[1-9]> <col:[1-9]> <val:[1-9]>"])
+    cmd_error(vec!["Usage: <row> <col> <val>"])
 }
 
 fn cmd_clear_cell_value(args: Vec<&str>) -> InputCommand {
     if args.len() == 3 {
-        if let Ok(row) = read_one_digit(args[1]) {
-            if let Ok(col) = read_one_digit(args[2]) {
+        if let Ok(row) = read_number(args[1]) {
+            if let Ok(col) = read_number(args[2]) {
                 return Box::new(move |game| {
                     match game.fill_cell((row - 1) as usize, (col - 1) as usize, 0) {
-                        Ok(_) => game.set_message(format!("[{},{}] cleared", row, col)),
+                        Ok(_) => match game.restorable_notes() {
+                            Some((val, count)) => game.set_message(format!(
+                                "[{},{}] cleared, 'note restore' puts {} back in {} note(s)",
+                                row,
+                                col,
+                                value_to_char(val),
+                                count
+                            )),
+                            None => game.set_message(format!("[{},{}] cleared", row, col)),
+                        },
                         Err(e) => {
                             game.set_message(format!("Error: {} (clear: [{},{}])", e, row, col))
                         }
@@ -115,7 +177,179 @@ fn cmd_clear_cell_value(args: Vec<&str>) -> InputCommand {
             }
         }
     }
-    cmd_error(vec!["Usage: clear <row:[1-9]> <col:[1-9]>"])
+    cmd_error(vec!["Usage: clear <row> <col>"])
+}
+
+fn cmd_note(args: Vec<&str>) -> InputCommand {
+    let cell = match args.len() {
+        2 => Ok(None),
+        4 => read_cell(args[2], args[3]).map(Some),
+        _ => Err(()),
+    };
+
+    match (args.get(1), cell) {
+        (Some(&"restore"), Ok(None)) => Box::new(|game| match game.restore_notes() {
+            Ok(_) => game.set_message(String::from("Notes restored")),
+            Err(e) => game.set_message(format!("Error: {} (note restore)", e)),
+        }),
+        (Some(&"clear"), Ok(cell)) => Box::new(move |game| match game.clear_notes(cell) {
+            Ok(_) => game.set_message(String::from("Notes cleared")),
+            Err(e) => game.set_message(format!("Error: {} (note clear)", e)),
+        }),
+        (Some(&"fill"), Ok(cell)) => Box::new(move |game| match game.fill_notes(cell) {
+            Ok(_) => game.set_message(String::from("Notes filled")),
+            Err(e) => game.set_message(format!("Error: {} (note fill)", e)),
+        }),
+        _ if args.len() == 4 => {
+            if let (Ok((row, col)), Ok(val)) = (read_cell(args[1], args[2]), read_value(args[3])) {
+                return Box::new(move |game| match game.toggle_note(row, col, val) {
+                    Ok(_) => game.set_message(format!(
+                        "Note {} toggled in [{},{}]",
+                        value_to_char(val),
+                        row + 1,
+                        col + 1
+                    )),
+                    Err(e) => game.set_message(format!(
+                        "Error: {} (note: [{},{}] {})",
+                        e,
+                        row + 1,
+                        col + 1,
+                        value_to_char(val)
+                    )),
+                });
+            }
+            cmd_error(vec!["Usage: note <row> <col> <val>"])
+        }
+        _ => cmd_error(vec!["Usage: note [clear|fill] <row> <col>"]),
+    }
+}
+
+fn cmd_load(args: Vec<&str>) -> InputCommand {
+    if args.len() == 2 {
+        let source = String::from(args[1]);
+        return Box::new(move |game| match game.load(&source) {
+            Ok(_) => game.set_message(String::from("Puzzle loaded.")),
+            Err(e) => game.set_message(format!("Error: {} (load: {})", e, source)),
+        });
+    }
+    cmd_error(vec!["Usage: load <puzzle|path>"])
+}
+
+fn cmd_export(_args: Vec<&str>) -> InputCommand {
+    Box::new(|game| {
+        let (givens, entries) = game.export();
+        game.set_message(format!("Givens:  {}\nEntries: {}", givens, entries));
+    })
+}
+
+fn cmd_save(args: Vec<&str>) -> InputCommand {
+    let path = String::from(*args.get(1).unwrap_or(&SAVE_PATH));
+    Box::new(move |game| match game.save(&path) {
+        Ok(_) => game.set_message(format!("Game saved to {}.", path)),
+        Err(e) => game.set_message(format!("Error: {} (save: {})", e, path)),
+    })
+}
+
+fn cmd_resume(args: Vec<&str>) -> InputCommand {
+    let path = String::from(*args.get(1).unwrap_or(&SAVE_PATH));
+    Box::new(move |game| match game.resume(&path) {
+        Ok(_) => game.set_message(format!("Game resumed from {}.", path)),
+        Err(e) => game.set_message(format!("Error: {} (resume: {})", e, path)),
+    })
+}
+
+fn cmd_view(args: Vec<&str>) -> InputCommand {
+    let view = match args.get(1).map(|s| s.to_lowercase()).as_deref() {
+        Some("values") => View::Values,
+        Some("notes") => View::Notes,
+        Some("candidates") => View::Candidates,
+        _ => return cmd_error(vec!["Usage: view [values|notes|candidates]"]),
+    };
+    Box::new(move |game| {
+        game.set_view(view);
+        game.set_message(String::new());
+    })
+}
+
+fn cmd_set(args: Vec<&str>) -> InputCommand {
+    let on = match args.get(2) {
+        Some(&"on") => Some(true),
+        Some(&"off") => Some(false),
+        None => None,
+        _ => return cmd_error(vec!["Usage: set [autonotes|strict] <on|off>"]),
+    };
+    let (getter, setter): Setting = match args.get(1) {
+        Some(&"autonotes") => (Game::auto_notes, Game::set_auto_notes),
+        Some(&"strict") => (Game::strict, Game::set_strict),
+        _ => return cmd_error(vec!["Usage: set [autonotes|strict] <on|off>"]),
+    };
+
+    let setting = String::from(args[1]);
+    Box::new(move |game| {
+        if let Some(on) = on {
+            setter(game, on);
+        }
+        let state = if getter(game) { "on" } else { "off" };
+        game.set_message(format!("{} is {}", setting, state));
+    })
+}
+
+fn cmd_undo(args: Vec<&str>) -> InputCommand {
+    match read_count(&args) {
+        Ok(count) => Box::new(move |game| match game.undo(count) {
+            0 => game.set_message(String::from("Nothing to undo.")),
+            undone => game.set_message(format!("{} move(s) undone", undone)),
+        }),
+        Err(_) => cmd_error(vec!["Usage: undo <count>"]),
+    }
+}
+
+fn cmd_redo(args: Vec<&str>) -> InputCommand {
+    match read_count(&args) {
+        Ok(count) => Box::new(move |game| match game.redo(count) {
+            0 => game.set_message(String::from("Nothing to redo.")),
+            redone => game.set_message(format!("{} move(s) redone", redone)),
+        }),
+        Err(_) => cmd_error(vec!["Usage: redo <count>"]),
+    }
+}
+
+fn cmd_check(_args: Vec<&str>) -> InputCommand {
+    Box::new(|game| match game.check() {
+        0 => game.set_message(String::from("No mistakes so far.")),
+        count => game.set_message(format!("{} wrong entry(ies) highlighted", count)),
+    })
+}
+
+fn cmd_lives(args: Vec<&str>) -> InputCommand {
+    let max_mistakes = match args.get(1) {
+        None => {
+            return Box::new(|game| match game.max_mistakes() {
+                Some(max) => game.set_message(format!("{}/{} mistakes", game.mistakes(), max)),
+                None => game.set_message(String::from("No mistake limit")),
+            })
+        }
+        Some(&"off") if args.len() == 2 => None,
+        Some(arg) if args.len() == 2 => match arg.parse::<usize>() {
+            Ok(max) if max > 0 => Some(max),
+            _ => return cmd_error(vec!["Usage: lives <count|off>"]),
+        },
+        _ => return cmd_error(vec!["Usage: lives <count|off>"]),
+    };
+    Box::new(move |game| {
+        game.set_max_mistakes(max_mistakes);
+        match max_mistakes {
+            Some(max) => game.set_message(format!("The game ends after {} mistakes", max)),
+            None => game.set_message(String::from("No mistake limit")),
+        }
+    })
+}
+
+fn cmd_hint(_args: Vec<&str>) -> InputCommand {
+    Box::new(|game| match game.hint() {
+        Some(hint) => game.set_message(hint),
+        None => game.set_message(String::from("No hint found.")),
+    })
 }
 
 fn cmd_solve(_args: Vec<&str>) -> InputCommand {
@@ -142,11 +376,41 @@ fn cmd_quit(_args: Vec<&str>) -> InputCommand {
     })
 }
 
-fn read_one_digit(input: &str) -> Result<u8, ()> {
+/// Read the optional count following a command, 1 by default.
+fn read_count(args: &[&str]) -> Result<usize, ()> {
+    match args.get(1) {
+        Some(arg) => match arg.parse::<usize>() {
+            Ok(count) if count > 0 && args.len() == 2 => Ok(count),
+            _ => Err(()),
+        },
+        None => Ok(1),
+    }
+}
+
+/// Read a row and a column, from 1 to 9, as indexes of the board.
+fn read_cell(row: &str, col: &str) -> Result<(usize, usize), ()> {
+    Ok((
+        read_number(row)? as usize - 1,
+        read_number(col)? as usize - 1,
+    ))
+}
+
+/// Read a row, a column or a value between 1 and the size of the largest board. The board
+/// refuses the ones outside of its own size.
+fn read_number(input: &str) -> Result<u8, ()> {
     if let Ok(val) = input.parse::<u8>() {
-        if (1..=BOARD_SIZE as u8).contains(&val) {
+        if (1..=MAX_BOARD_SIZE as u8).contains(&val) {
             return Ok(val);
         }
     }
     Result::Err(())
 }
+
+/// Read a value as a number or as the letter showing it, like `G` for 16.
+fn read_value(input: &str) -> Result<u8, ()> {
+    let mut chars = input.chars();
+    match (chars.next().and_then(char_to_value), chars.next()) {
+        (Some(val), None) => Ok(val),
+        _ => read_number(input),
+    }
+}
@@ -3,12 +3,14 @@ extern crate lazy_static;
 
 use crate::game::Game;
 
-mod board;
+pub mod board;
 mod game;
-mod generator;
+pub mod generator;
 mod input;
+pub mod rating;
 mod render;
-mod solver;
+mod save;
+pub mod solver;
 
 pub fn play() {
     Game::new().play();
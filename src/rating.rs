@@ -0,0 +1,172 @@
+use std::collections::BTreeMap;
+
+use crate::board::{Board, BOARD_SIZE};
+use crate::generator::Difficulty;
+use crate::solver::{LogicalSolver, Technique};
+
+/// Score given to the boards the logical solver can't finish.
+pub const GUESSING: f32 = 10.0;
+
+/// Extra score for each step using a technique harder than a single, up to `MAX_BONUS`.
+const STEP_BONUS: f32 = 0.05;
+const MAX_BONUS: f32 = 0.4;
+
+/// Difficulty of a board, from the techniques needed to solve it.
+pub struct Rating {
+    score: f32,
+    hardest: Option<Technique>,
+    counts: BTreeMap<Technique, usize>,
+    solved: bool,
+}
+
+impl Rating {
+    pub fn new(board: &Board) -> Rating {
+        let mut nb_empty_cell = board
+            .shape()
+            .positions()
+            .filter(|(row, col)| board.get_value(*row, *col).is_none())
+            .count();
+        let mut counts = BTreeMap::new();
+        for step in LogicalSolver::new().steps(board) {
+            nb_empty_cell -= step.placements.len();
+            *counts.entry(step.technique).or_insert(0) += 1;
+        }
+
+        let hardest = counts.keys().last().copied();
+        let advanced_steps: usize = counts
+            .iter()
+            .filter(|(technique, _)| !Self::is_single(**technique))
+            .map(|(_, count)| *count)
+            .sum();
+        let bonus = (advanced_steps as f32 * STEP_BONUS).min(MAX_BONUS);
+
+        let solved = nb_empty_cell == 0;
+        let score = match hardest {
+            _ if !solved => GUESSING,
+            Some(technique) => Self::weight(technique) + bonus,
+            None => 0.0,
+        };
+
+        Rating {
+            score,
+            hardest,
+            counts,
+            solved,
+        }
+    }
+
+    /// Whether the scores mean something for the board. The weights of the techniques are set
+    /// for 9x9 boards with givens: a killer sudoku needs more cage reasoning than the logical
+    /// solver has, and the singles of a smaller board are far easier.
+    pub fn is_rated(board: &Board) -> bool {
+        board.size() == BOARD_SIZE && board.cages().is_empty()
+    }
+
+    pub fn score(&self) -> f32 {
+        self.score
+    }
+
+    /// The hardest technique used by the logical solver.
+    pub fn hardest(&self) -> Option<Technique> {
+        self.hardest
+    }
+
+    /// How many steps used each technique.
+    pub fn counts(&self) -> &BTreeMap<Technique, usize> {
+        &self.counts
+    }
+
+    /// Whether the logical solver finished the board, without guessing.
+    pub fn is_solved(&self) -> bool {
+        self.solved
+    }
+
+    /// The difficulty whose band contains the score, if any.
+    pub fn difficulty(&self) -> Option<Difficulty> {
+        Difficulty::ALL
+            .iter()
+            .find(|difficulty| {
+                let (min, max) = Self::band(**difficulty);
+                (min..max).contains(&self.score)
+            })
+            .copied()
+    }
+
+    /// The scores of a difficulty, from inclusive min to exclusive max.
+    pub fn band(difficulty: Difficulty) -> (f32, f32) {
+        match difficulty {
+            Difficulty::Easy => (0.0, 2.0),
+            Difficulty::Medium => (2.0, 3.0),
+            Difficulty::Hard => (3.0, 4.0),
+            Difficulty::Expert => (4.0, GUESSING),
+        }
+    }
+
+    /// Score of a technique, on the same scale as Sudoku Explainer.
+    fn weight(technique: Technique) -> f32 {
+        match technique {
+            Technique::HiddenSingle => 1.5,
+            Technique::NakedSingle => 2.3,
+            Technique::PointingPair => 2.6,
+            Technique::BoxLineReduction => 2.8,
+            Technique::CageCombination => 2.9,
+            Technique::NakedPair => 3.0,
+            Technique::XWing => 3.2,
+            Technique::HiddenPair => 3.4,
+            Technique::NakedTriple => 3.6,
+            Technique::Swordfish => 3.8,
+            Technique::HiddenTriple => 4.0,
+            Technique::XYWing => 4.2,
+            Technique::SimpleColoring => 4.5,
+            Technique::NakedQuad => 5.0,
+            Technique::HiddenQuad => 5.4,
+        }
+    }
+
+    fn is_single(technique: Technique) -> bool {
+        technique == Technique::HiddenSingle || technique == Technique::NakedSingle
+    }
+}
+
+#[cfg(test)]
+mod tests {
+
+    use crate::board::{Board, Cage, Shape};
+    use crate::generator::Difficulty;
+    use crate::rating::{Rating, GUESSING};
+    use crate::solver::Technique;
+
+    #[test]
+    fn test_rating() {
+        let board: Board =
+            "100000569492056108056109240009640801064010000218035604040500016905061402621000005"
+                .parse()
+                .unwrap();
+        let rating = Rating::new(&board);
+        assert!(rating.is_solved());
+        assert_eq!(Some(Technique::XWing), rating.hardest());
+        assert_eq!(Some(&1), rating.counts().get(&Technique::XWing));
+        assert_eq!(Some(Difficulty::Hard), rating.difficulty());
+    }
+
+    #[test]
+    fn test_rating_guessing() {
+        let board: Board =
+            "800000000003600000070090200050007000000045700000100030001000068008500010090000400"
+                .parse()
+                .unwrap();
+        let rating = Rating::new(&board);
+        assert!(!rating.is_solved());
+        assert_eq!(GUESSING, rating.score());
+        assert_eq!(None, rating.difficulty());
+    }
+
+    #[test]
+    fn test_is_rated() {
+        let mut board = Board::new();
+        assert!(Rating::is_rated(&board));
+        board.set_cages(vec![Cage::new(vec![(0, 0)], 1)]).unwrap();
+        assert!(!Rating::is_rated(&board));
+        assert!(!Rating::is_rated(&Board::with_shape(Shape::new(2))));
+    }
+}
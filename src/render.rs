@@ -5,9 +5,8 @@ use termion::clear;
 use termion::color::*;
 use termion::screen::AlternateScreen;
 
-use crate::board::BOARD_SIZE;
-use crate::board::{Board, BOARD_BOX_SIZE};
-use crate::game::Game;
+use crate::board::{value_to_char, Board, FreeNumberMask, Shape, Unit};
+use crate::game::{Game, View};
 use core::cmp;
 
 const SCREEN_WIDTH: usize = 80;
@@ -18,6 +17,79 @@ pub trait Render {
 
 pub struct ConsoleRender {
     screen: AlternateScreen<Stdout>,
+    width: usize,
+}
+
+/// Size of the board on screen, which depends on the view and on the shape of the board.
+struct Layout {
+    view: View,
+    size: usize,
+    // marks drawn on each line of a cell in the views showing notes or candidates, which are laid
+    // out like the cells of a box
+    line_marks: usize,
+    column_size: usize,
+    row_height: usize,
+    margin_width: usize,
+}
+
+impl Layout {
+    fn new(view: View, shape: &Shape) -> Layout {
+        let line_marks = shape.box_cols();
+        let (column_size, row_height) = match view {
+            View::Values => (3, 1),
+            View::Notes | View::Candidates => (2 * line_marks + 1, shape.box_rows()),
+        };
+        Layout {
+            view,
+            size: shape.size(),
+            line_marks,
+            column_size,
+            row_height,
+            margin_width: 0,
+        }
+    }
+
+    fn board_width(&self) -> usize {
+        (self.size + 2) * self.column_size + (self.size + 2) + 1
+    }
+
+    /// The marks to draw in the cell when it is empty.
+    fn marks(&self, game: &Game, row: usize, col: usize) -> Option<FreeNumberMask> {
+        match self.view {
+            View::Values => None,
+            View::Notes => Some(game.board().get_notes(row, col)),
+            View::Candidates => Some(game.board().get_candidates(row, col)),
+        }
+    }
+}
+
+/// Colors of the lines of the board.
+struct BoardColors<'a> {
+    r#box: &'a dyn Color,
+    cage: &'a dyn Color,
+    cell: &'a dyn Color,
+}
+
+impl<'a> BoardColors<'a> {
+    /// The color and the character of a border: the cage borders come first, then the box
+    /// borders. In killer sudoku, the borders between the cells of a cage are left blank.
+    fn border(
+        &self,
+        box_border: bool,
+        cage_border: bool,
+        board: &Board,
+        line: char,
+    ) -> (&'a dyn Color, char) {
+        if cage_border {
+            (self.cage, line)
+        } else if box_border {
+            (self.r#box, line)
+        } else if !board.cages().is_empty() {
+            (self.cell, ' ')
+        } else {
+            (self.cell, line)
+        }
+    }
 }
 
 #[derive(Copy, Clone)]
@@ -37,10 +109,16 @@ impl ConsoleRender {
     pub fn new() -> ConsoleRender {
         ConsoleRender {
             screen: AlternateScreen::from(stdout()),
+            width: SCREEN_WIDTH,
         }
     }
 
     fn write(&mut self, game: &Game) -> Result<(), Box<dyn Error>> {
+        // the screen widens when the board doesn't fit, to keep everything centered on it
+        let mut layout = Layout::new(game.view(), game.board().shape());
+        self.width = cmp::max(SCREEN_WIDTH, layout.board_width());
+        layout.margin_width = (self.width - layout.board_width()) / 2;
+
         writeln!(self.screen, "{}", clear::All)?;
         self.screen.flush()?;
         self.write_line_break()?;
@@ -48,10 +126,12 @@ impl ConsoleRender {
         self.write_lines(game.headers(), Align::CENTER)?;
         self.write_line_break()?;
         self.write_line_break()?;
-        self.write_board(game.board())?;
+        self.write_board(game, &layout)?;
         self.write_line_break()?;
         self.write_line_break()?;
-        self.write_line(&game.message(), Align::CENTER, true)?;
+        for line in game.message().lines() {
+            self.write_line(line, Align::CENTER, true)?;
+        }
         self.write_line_break()?;
         self.write_line_break()?;
         self.write_lines(&game.footers(), Align::LEFT)?;
@@ -65,47 +145,69 @@ impl ConsoleRender {
     fn write_board_header(
         &mut self,
         color: &dyn Color,
-        margin_width: usize,
+        layout: &Layout,
     ) -> Result<(), Box<dyn Error>> {
+        let width = layout.column_size;
         write!(self.screen, "{}", Fg(color))?;
-        write!(self.screen, "{:width$}", "", width = margin_width)?;
-        write!(self.screen, " {:^3}", " ")?;
-        for val in 1..=BOARD_SIZE {
-            write!(self.screen, " {:^3}", val)?;
+        write!(self.screen, "{:width$}", "", width = layout.margin_width)?;
+        write!(self.screen, " {:^width$}", " ", width = width)?;
+        for col in 1..=layout.size {
+            write!(self.screen, " {:^width$}", col, width = width)?;
         }
-        write!(self.screen, " {:^3} ", " ")?;
-        write!(self.screen, "{:width$}", "", width = margin_width)?;
+        write!(self.screen, " {:^width$} ", " ", width = width)?;
+        write!(self.screen, "{:width$}", "", width = layout.margin_width)?;
         write!(self.screen, "{}", Fg(Reset))?;
         writeln!(self.screen)?;
         Ok(())
     }
 
+    /// Draw the line above the row, or below the board when `row` is the size of the board. The
+    /// borders between two boxes are drawn with the box color, and in killer sudoku the borders
+    /// between two cages with the cage color, with the sum of each cage above its first cell.
     fn write_board_line(
         &mut self,
-        main_color: &dyn Color,
-        sub_color: &dyn Color,
-        margin_width: usize,
+        board: &Board,
+        row: usize,
+        colors: &BoardColors,
+        layout: &Layout,
     ) -> Result<(), Box<dyn Error>> {
-        write!(self.screen, "{}", Fg(main_color))?;
-        write!(self.screen, "{:width$}", "", width = margin_width)?;
-        write!(self.screen, " {:^3}", " ")?;
-        for col in 0..BOARD_SIZE {
-            let color = if col % BOARD_BOX_SIZE == 0 {
-                sub_color
-            } else {
-                main_color
-            };
-            write!(self.screen, "{}{}", Fg(color), "+",)?;
-            write!(self.screen, "{}{}", Fg(main_color), "---")?;
+        let width = layout.column_size;
+        let (above, below) = (row.checked_sub(1), Some(row));
+
+        write!(self.screen, "{:width$}", "", width = layout.margin_width)?;
+        write!(self.screen, " {:^width$}", " ", width = width)?;
+        for col in 0..=layout.size {
+            let (left, right) = (col.checked_sub(1), Some(col));
+            let sides = [
+                ((above, left), (above, right)),
+                ((below, left), (below, right)),
+                ((above, left), (below, left)),
+                ((above, right), (below, right)),
+            ];
+            let (color, corner) = colors.border(
+                sides.iter().any(|(a, b)| is_border(*a, *b, board)),
+                sides.iter().any(|(a, b)| is_cage_border(*a, *b, board)),
+                board,
+                '+',
+            );
+            write!(self.screen, "{}{}", Fg(color), corner)?;
+            if col < layout.size {
+                let (a, b) = ((above, right), (below, right));
+                let (color, line) = colors.border(
+                    is_border(a, b, board),
+                    is_cage_border(a, b, board),
+                    board,
+                    '-',
+                );
+                let segment = match cage_sum(row, col, board) {
+                    Some(sum) => format!("{:-<width$}", sum, width = width),
+                    None => line.to_string().repeat(width),
+                };
+                write!(self.screen, "{}{}", Fg(color), segment)?;
+            }
         }
-        write!(
-            self.screen,
-            "{}+{}{:^3} ",
-            Fg(sub_color),
-            Fg(main_color),
-            " "
-        )?;
-        write!(self.screen, "{:width$}", "", width = margin_width)?;
+        write!(self.screen, "{:^width$} ", " ", width = width)?;
+        write!(self.screen, "{:width$}", "", width = layout.margin_width)?;
         write!(self.screen, "{}", Fg(Reset))?;
         writeln!(self.screen)?;
         Ok(())
@@ -113,63 +215,112 @@ impl ConsoleRender {
 
     fn write_board_row(
         &mut self,
-        board: &Board,
+        game: &Game,
         row: usize,
-        box_color: &dyn Color,
-        cell_color: &dyn Color,
-        margin_width: usize,
+        colors: &BoardColors,
+        layout: &Layout,
     ) -> Result<(), Box<dyn Error>> {
-        write!(self.screen, "{:width$}", "", width = margin_width)?;
-        write!(self.screen, " {}{:^3}", Fg(cell_color), row + 1)?;
-        for col in 0..BOARD_SIZE {
-            let color = if col % BOARD_BOX_SIZE == 0 {
-                box_color
+        let board = game.board();
+        let width = layout.column_size;
+        for line in 0..layout.row_height {
+            // the row number and the values are drawn on the middle line of the row
+            let middle = line == layout.row_height / 2;
+            let label = if middle {
+                (row + 1).to_string()
             } else {
-                cell_color
+                String::new()
             };
-            let val = board
-                .get_value(row, col)
-                .map_or(String::new(), |val| val.to_string());
-            write!(self.screen, "{}{}", Fg(color), "|",)?;
-            if board.is_fixed_value(row, col) {
-                write!(self.screen, "{}{:^3}", Fg(Rgb(102, 178, 255)), val)?;
-            } else {
-                write!(self.screen, "{}{:^3}", Fg(Reset), val)?;
+
+            write!(self.screen, "{:width$}", "", width = layout.margin_width)?;
+            write!(
+                self.screen,
+                " {}{:^width$}",
+                Fg(colors.cell),
+                label,
+                width = width
+            )?;
+            for col in 0..layout.size {
+                let (a, b) = ((Some(row), col.checked_sub(1)), (Some(row), Some(col)));
+                let (color, border) = colors.border(
+                    is_border(a, b, board),
+                    is_cage_border(a, b, board),
+                    board,
+                    '|',
+                );
+                write!(self.screen, "{}{}", Fg(color), border)?;
+                if game.highlights().contains(&(row, col)) {
+                    write!(self.screen, "{}", Bg(Rgb(90, 90, 0)))?;
+                } else if is_diagonal(row, col, board) {
+                    write!(self.screen, "{}", Bg(Rgb(40, 40, 80)))?;
+                }
+                match (board.get_value(row, col), layout.marks(game, row, col)) {
+                    (Some(val), _) if middle => {
+                        let color: &dyn Color = if board.is_conflict(row, col) {
+                            &LightRed
+                        } else if board.is_fixed_value(row, col) {
+                            &Rgb(102, 178, 255)
+                        } else {
+                            &Reset
+                        };
+                        write!(
+                            self.screen,
+                            "{}{:^width$}",
+                            Fg(color),
+                            value_to_char(val),
+                            width = width
+                        )?
+                    }
+                    (None, Some(marks)) => {
+                        let marks: Vec<String> = (1..=layout.line_marks)
+                            .map(|i| (line * layout.line_marks + i) as u8)
+                            .map(|val| match marks.contains_value(val) {
+                                true => value_to_char(val).to_string(),
+                                false => String::from(" "),
+                            })
+                            .collect();
+                        write!(
+                            self.screen,
+                            "{}{:^width$}",
+                            Fg(Rgb(160, 160, 160)),
+                            marks.join(" "),
+                            width = width
+                        )?
+                    }
+                    _ => write!(self.screen, "{:width$}", "", width = width)?,
+                }
+                write!(self.screen, "{}", Bg(Reset))?;
             }
+            let (a, b) = ((Some(row), Some(layout.size - 1)), (Some(row), None));
+            let (color, _) = colors.border(true, is_cage_border(a, b, board), board, '|');
+            write!(
+                self.screen,
+                "{}|{}{:^width$} ",
+                Fg(color),
+                Fg(colors.cell),
+                label,
+                width = width
+            )?;
+            write!(self.screen, "{:width$}", "", width = layout.margin_width)?;
+            write!(self.screen, "{}", Fg(Reset))?;
+            writeln!(self.screen)?;
         }
-        write!(
-            self.screen,
-            "{}|{}{:^3} ",
-            Fg(box_color),
-            Fg(cell_color),
-            row + 1
-        )?;
-        write!(self.screen, "{:width$}", "", width = margin_width)?;
-        write!(self.screen, "{}", Fg(Reset))?;
-        writeln!(self.screen)?;
         Ok(())
     }
 
-    fn write_board(&mut self, board: &Board) -> Result<(), Box<dyn Error>> {
-        let box_color = LightGreen;
-        let cell_color = Rgb(127, 127, 127);
-
-        let column_size = 3;
-        let board_width = (BOARD_SIZE + 2) * column_size + (BOARD_SIZE + 2) + 1;
-        let margin_width = cmp::max(0, SCREEN_WIDTH - board_width) / 2;
+    fn write_board(&mut self, game: &Game, layout: &Layout) -> Result<(), Box<dyn Error>> {
+        let colors = BoardColors {
+            r#box: &LightGreen,
+            cage: &LightYellow,
+            cell: &Rgb(127, 127, 127),
+        };
 
-        self.write_board_header(&cell_color, margin_width)?;
-        for row in 0..BOARD_SIZE {
-            let color: &dyn Color = if row % 3 == 0 {
-                &box_color
-            } else {
-                &cell_color
-            };
-            self.write_board_line(&color, &box_color, margin_width)?;
-            self.write_board_row(board, row, &box_color, &cell_color, margin_width)?;
+        self.write_board_header(colors.cell, layout)?;
+        for row in 0..layout.size {
+            self.write_board_line(game.board(), row, &colors, layout)?;
+            self.write_board_row(game, row, &colors, layout)?;
         }
-        self.write_board_line(&box_color, &box_color, margin_width)?;
-        self.write_board_header(&cell_color, margin_width)?;
+        self.write_board_line(game.board(), layout.size, &colors, layout)?;
+        self.write_board_header(colors.cell, layout)?;
         Ok(())
     }
 
@@ -191,8 +342,8 @@ impl ConsoleRender {
         }
         match align {
             Align::LEFT => write!(self.screen, "{}", text)?,
-            Align::CENTER => write!(self.screen, "{:^width$}", text, width = SCREEN_WIDTH)?,
-            //Align::RIGHT => write!(self.screen, "{:>width$}", text, width = SCREEN_WIDTH)?
+            Align::CENTER => write!(self.screen, "{:^width$}", text, width = self.width)?,
+            //Align::RIGHT => write!(self.screen, "{:>width$}", text, width = self.width)?
         }
         if line_break {
             self.write_line_break()?;
@@ -206,3 +357,56 @@ impl ConsoleRender {
         Ok(())
     }
 }
+
+/// Whether a box border, or the border of a jigsaw region, separates two neighbor cells, given as
+/// (row, col) with `None` or the size of the board outside of it: the edges of the board are box
+/// borders.
+fn is_border(
+    a: (Option<usize>, Option<usize>),
+    b: (Option<usize>, Option<usize>),
+    board: &Board,
+) -> bool {
+    let box_index = |cell| match cell {
+        (Some(row), Some(col)) if row < board.size() && col < board.size() => {
+            Some(board.shape().box_index(row, col))
+        }
+        _ => None,
+    };
+    box_index(a) != box_index(b)
+}
+
+/// Whether a cage border separates two neighbor cells, given as in `is_border`. The cells outside
+/// of the cages, like the cells outside of the board, are all apart from the cages.
+fn is_cage_border(
+    a: (Option<usize>, Option<usize>),
+    b: (Option<usize>, Option<usize>),
+    board: &Board,
+) -> bool {
+    let first_cell = |cell| match cell {
+        (Some(row), Some(col)) if row < board.size() && col < board.size() => {
+            board.get_cage(row, col).map(|cage| cage.cells()[0])
+        }
+        _ => None,
+    };
+    !board.cages().is_empty() && first_cell(a) != first_cell(b)
+}
+
+/// The sum of the cage whose first cell is the cell, drawn on the line above it.
+fn cage_sum(row: usize, col: usize, board: &Board) -> Option<u32> {
+    if row == board.size() {
+        return None;
+    }
+    board
+        .get_cage(row, col)
+        .filter(|cage| cage.cells()[0] == (row, col))
+        .map(|cage| cage.sum())
+}
+
+/// Whether the cell is on a diagonal holding every value, in the diagonal variant.
+fn is_diagonal(row: usize, col: usize, board: &Board) -> bool {
+    board
+        .shape()
+        .units_containing(row, col)
+        .iter()
+        .any(|unit| matches!(unit, Unit::Diagonal(_)))
+}
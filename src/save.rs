@@ -0,0 +1,410 @@
+use std::env;
+use std::fmt;
+use std::path::PathBuf;
+use std::str::FromStr;
+use std::time::Duration;
+
+use crate::board::{char_to_value, value_to_char, Board, Cage, FreeNumberMask, Shape};
+use crate::generator::Difficulty;
+
+/// File where the game is saved after every command, in the per-user data directory: the
+/// `XDG_DATA_HOME` directory, `~/.local/share` or `%APPDATA%`.
+pub fn autosave_path() -> Option<PathBuf> {
+    let var = |name| env::var_os(name).filter(|value| !value.is_empty());
+    let dir = var("XDG_DATA_HOME")
+        .map(PathBuf::from)
+        .or_else(|| var("HOME").map(|home| PathBuf::from(home).join(".local").join("share")))
+        .or_else(|| var("APPDATA").map(PathBuf::from))?;
+    Some(dir.join("sudoku").join("autosave"))
+}
+
+/// The state of a game in progress, written as one `key=value` line per field.
+pub struct SavedGame {
+    pub board: Board,
+    pub elapsed: Duration,
+    pub difficulty: Option<Difficulty>,
+    pub score: Option<f32>,
+    pub seed: Option<u64>,
+    pub mistakes: usize,
+    pub max_mistakes: Option<usize>,
+}
+
+impl fmt::Display for SavedGame {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        if self.board.shape().diagonals() {
+            writeln!(f, "variant=x")?;
+        }
+        if let Some(regions) = self.board.shape().regions() {
+            let regions: String = regions
+                .iter()
+                .map(|r| value_to_char(*r as u8 + 1))
+                .collect();
+            writeln!(f, "regions={}", regions)?;
+        }
+        if !self.board.cages().is_empty() {
+            let cages: Vec<String> = self.board.cages().iter().map(Cage::to_string).collect();
+            writeln!(f, "cages={}", cages.join(";"))?;
+        }
+        writeln!(f, "givens={}", self.board)?;
+        writeln!(f, "entries={}", self.board.to_entries_string())?;
+        writeln!(f, "elapsed={}", self.elapsed.as_secs())?;
+        writeln!(
+            f,
+            "difficulty={}",
+            self.difficulty.map_or("Unrated", |d| d.into())
+        )?;
+        if let Some(score) = self.score {
+            writeln!(f, "score={:.1}", score)?;
+        }
+        if let Some(seed) = self.seed {
+            writeln!(f, "seed={}", seed)?;
+        }
+        if let Some(max_mistakes) = self.max_mistakes {
+            writeln!(f, "mistakes={}/{}", self.mistakes, max_mistakes)?;
+        }
+        if self.board.has_notes() {
+            let notes: Vec<String> = self
+                .board
+                .shape()
+                .positions()
+                .map(|(row, col)| match self.board.get_value(row, col) {
+                    // a filled cell keeps its notes for when it is cleared, but they can't be set
+                    // back on a filled cell
+                    Some(_) => String::new(),
+                    None => self
+                        .board
+                        .get_notes(row, col)
+                        .values()
+                        .into_iter()
+                        .map(value_to_char)
+                        .collect(),
+                })
+                .collect();
+            writeln!(f, "notes={}", notes.join(","))?;
+        }
+        Ok(())
+    }
+}
+
+impl FromStr for SavedGame {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let mut givens = None;
+        let mut diagonals = false;
+        let mut regions = None;
+        let mut cages = vec![];
+        let mut entries = None;
+        let mut notes = None;
+        let mut saved = SavedGame {
+            board: Board::new(),
+            elapsed: Duration::default(),
+            difficulty: None,
+            score: None,
+            seed: None,
+            mistakes: 0,
+            max_mistakes: None,
+        };
+
+        for line in s.lines().map(str::trim).filter(|line| !line.is_empty()) {
+            let mut parts = line.splitn(2, '=');
+            let (key, value) = match (parts.next(), parts.next()) {
+                (Some(key), Some(value)) => (key.trim(), value.trim()),
+                _ => return Err(format!("Invalid line '{}'", line)),
+            };
+
+            let invalid = || format!("Invalid {} '{}'", key, value);
+            match key {
+                "variant" if value == "x" => diagonals = true,
+                "variant" => return Err(invalid()),
+                "regions" => {
+                    regions = Some(
+                        value
+                            .chars()
+                            .map(|c| char_to_value(c).map(|val| val as usize - 1))
+                            .collect::<Option<Vec<usize>>>()
+                            .ok_or_else(invalid)?,
+                    )
+                }
+                "cages" => {
+                    cages = value
+                        .split(';')
+                        .map(str::parse::<Cage>)
+                        .collect::<Result<_, _>>()?
+                }
+                "givens" => givens = Some(String::from(value)),
+                "entries" => entries = Some(String::from(value)),
+                "elapsed" => {
+                    saved.elapsed = Duration::from_secs(value.parse().map_err(|_| invalid())?)
+                }
+                "difficulty" if value == "Unrated" => saved.difficulty = None,
+                "difficulty" => saved.difficulty = Some(value.parse()?),
+                "score" => saved.score = Some(value.parse().map_err(|_| invalid())?),
+                "seed" => saved.seed = Some(value.parse().map_err(|_| invalid())?),
+                "mistakes" => {
+                    let mut parts = value.splitn(2, '/').map(str::parse::<usize>);
+                    match (parts.next(), parts.next()) {
+                        (Some(Ok(mistakes)), Some(Ok(max_mistakes))) => {
+                            saved.mistakes = mistakes;
+                            saved.max_mistakes = Some(max_mistakes);
+                        }
+                        _ => return Err(invalid()),
+                    }
+                }
+                "notes" => notes = Some(String::from(value)),
+                _ => return Err(format!("Unknown key '{}'", key)),
+            }
+        }
+
+        // the givens tell the size of the board, but not its variant
+        let givens = givens.ok_or_else(|| String::from("Missing givens"))?;
+        let mut shape = Shape::from_one_line(&givens)?;
+        shape.set_diagonals(diagonals);
+        shape.set_regions(regions)?;
+        saved.board = Board::from_str_with_shape(&givens, shape)?;
+        saved.board.set_cages(cages)?;
+        if let Some(entries) = entries {
+            Self::read_entries(&mut saved.board, &entries)?;
+        }
+        if let Some(notes) = notes {
+            Self::read_notes(&mut saved.board, &notes)?;
+        }
+        Ok(saved)
+    }
+}
+
+impl SavedGame {
+    /// Read the user's entries in the one-line format, which may conflict with each other.
+    fn read_entries(board: &mut Board, entries: &str) -> Result<(), String> {
+        let values: Vec<char> = entries.chars().filter(|c| !c.is_whitespace()).collect();
+        let nb_cells = board.shape().nb_cells();
+        if values.len() != nb_cells {
+            return Err(format!(
+                "Expected {} entries, found {}",
+                nb_cells,
+                values.len()
+            ));
+        }
+
+        let allow_conflicts = board.allow_conflicts();
+        board.set_allow_conflicts(true);
+        for (i, c) in values.into_iter().enumerate() {
+            let (row, col) = (i / board.size(), i % board.size());
+            let result = match (c, char_to_value(c)) {
+                ('.', _) | ('0', _) => Ok(()),
+                (_, Some(val)) => board.set_value(row, col, val).map_err(String::from),
+                _ => Err(format!("Invalid character '{}'", c)),
+            };
+            result.map_err(|e| format!("{} (r{}c{})", e, row + 1, col + 1))?;
+        }
+        board.set_allow_conflicts(allow_conflicts);
+        Ok(())
+    }
+
+    /// Read the notes of each cell, as values separated by commas.
+    fn read_notes(board: &mut Board, notes: &str) -> Result<(), String> {
+        let cells: Vec<&str> = notes.split(',').collect();
+        let nb_cells = board.shape().nb_cells();
+        if cells.len() != nb_cells {
+            return Err(format!(
+                "Expected {} notes, found {}",
+                nb_cells,
+                cells.len()
+            ));
+        }
+
+        for (i, cell) in cells.into_iter().enumerate() {
+            let (row, col) = (i / board.size(), i % board.size());
+            let mut mask = FreeNumberMask::none();
+            for c in cell.chars() {
+                match char_to_value(c) {
+                    Some(val) if val as usize <= board.size() => {
+                        mask.set(FreeNumberMask::from_value(val))
+                    }
+                    _ => return Err(format!("Invalid note '{}' (r{}c{})", c, row + 1, col + 1)),
+                }
+            }
+            if !mask.is_none() {
+                board
+                    .set_notes(row, col, mask)
+                    .map_err(|e| format!("{} (r{}c{})", e, row + 1, col + 1))?;
+            }
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+
+    use std::time::Duration;
+
+    use crate::board::{Board, Cage, Shape};
+    use crate::generator::{BasicGenerator, Difficulty, Generator, RegionGenerator};
+    use crate::save::SavedGame;
+
+    const PUZZLE: &str =
+        "530070000600195000098000060800060003400803001700020006060000280000419005000080079";
+
+    #[test]
+    fn test_save_and_resume() {
+        let mut board = PUZZLE.parse::<Board>().unwrap();
+        board.set_value(0, 2, 4).unwrap();
+        board.toggle_note(8, 0, 1).unwrap();
+        board.toggle_note(8, 0, 3).unwrap();
+        let saved = SavedGame {
+            board,
+            elapsed: Duration::from_secs(754),
+            difficulty: Some(Difficulty::Hard),
+            score: Some(3.2),
+            seed: Some(42),
+            mistakes: 1,
+            max_mistakes: Some(3),
+        };
+
+        let resumed: SavedGame = saved.to_string().parse().unwrap();
+        assert_eq!(PUZZLE.replace("0", "."), resumed.board.to_string());
+        assert_eq!(Some(4), resumed.board.get_value(0, 2));
+        assert!(!resumed.board.is_fixed_value(0, 2));
+        assert_eq!(754, resumed.elapsed.as_secs());
+        assert_eq!(Some(Difficulty::Hard), resumed.difficulty);
+        assert_eq!(Some(3.2), resumed.score);
+        assert_eq!(Some(42), resumed.seed);
+        assert_eq!(1, resumed.mistakes);
+        assert_eq!(Some(3), resumed.max_mistakes);
+        assert_eq!(vec![1, 3], resumed.board.get_notes(8, 0).values());
+        assert!(resumed.board.get_notes(8, 1).is_none());
+    }
+
+    #[test]
+    fn test_save_and_resume_filled_notes() {
+        let mut board = PUZZLE.parse::<Board>().unwrap();
+        board.toggle_note(0, 2, 4).unwrap();
+        board.set_value(0, 2, 4).unwrap();
+        let saved = SavedGame {
+            board,
+            elapsed: Duration::from_secs(0),
+            difficulty: None,
+            score: None,
+            seed: None,
+            mistakes: 0,
+            max_mistakes: None,
+        };
+
+        let resumed: SavedGame = saved.to_string().parse().unwrap();
+        assert_eq!(Some(4), resumed.board.get_value(0, 2));
+    }
+
+    #[test]
+    fn test_save_and_resume_diagonals() {
+        let mut shape = Shape::default();
+        shape.set_diagonals(true);
+        let saved = SavedGame {
+            board: Board::from_str_with_shape(PUZZLE, shape).unwrap(),
+            elapsed: Duration::from_secs(0),
+            difficulty: None,
+            score: None,
+            seed: None,
+            mistakes: 0,
+            max_mistakes: None,
+        };
+
+        let resumed: SavedGame = saved.to_string().parse().unwrap();
+        assert!(resumed.board.shape().diagonals());
+        assert!(format!("variant=y\ngivens={}", PUZZLE)
+            .parse::<SavedGame>()
+            .is_err());
+    }
+
+    #[test]
+    fn test_save_and_resume_regions() {
+        let shape = RegionGenerator::with_seed(Shape::default(), Some(2))
+            .generate()
+            .unwrap();
+        let saved = SavedGame {
+            board: Board::with_shape(shape.clone()),
+            elapsed: Duration::from_secs(0),
+            difficulty: None,
+            score: None,
+            seed: None,
+            mistakes: 0,
+            max_mistakes: None,
+        };
+
+        let resumed: SavedGame = saved.to_string().parse().unwrap();
+        assert_eq!(&shape, resumed.board.shape());
+        assert!(format!("regions=123\ngivens={}", PUZZLE)
+            .parse::<SavedGame>()
+            .is_err());
+    }
+
+    #[test]
+    fn test_save_and_resume_regions_givens() {
+        let shape = RegionGenerator::with_seed(Shape::default(), Some(0))
+            .generate()
+            .unwrap();
+        let puzzle = BasicGenerator::with_shape(shape, Difficulty::Easy, Some(0))
+            .generate()
+            .unwrap();
+        let saved = SavedGame {
+            board: puzzle.board().clone(),
+            elapsed: Duration::from_secs(0),
+            difficulty: None,
+            score: None,
+            seed: None,
+            mistakes: 0,
+            max_mistakes: None,
+        };
+
+        let resumed: SavedGame = saved.to_string().parse().unwrap();
+        assert_eq!(saved.to_string(), resumed.to_string());
+    }
+
+    #[test]
+    fn test_save_and_resume_cages() {
+        let mut board = Board::new();
+        let cages = vec![
+            Cage::new(vec![(0, 0), (0, 1)], 3),
+            Cage::new(vec![(8, 8)], 9),
+        ];
+        board.set_cages(cages.clone()).unwrap();
+        board.set_value(0, 0, 1).unwrap();
+        let saved = SavedGame {
+            board,
+            elapsed: Duration::from_secs(0),
+            difficulty: None,
+            score: None,
+            seed: None,
+            mistakes: 0,
+            max_mistakes: None,
+        };
+
+        let resumed: SavedGame = saved.to_string().parse().unwrap();
+        assert_eq!(&cages[..], resumed.board.cages());
+        assert_eq!(Some(1), resumed.board.get_value(0, 0));
+        assert!(format!("cages=3:r1c1;\ngivens={}", PUZZLE)
+            .parse::<SavedGame>()
+            .is_err());
+    }
+
+    #[test]
+    fn test_resume_conflicts() {
+        let entries = format!("55{}", ".".repeat(79));
+        let saved: SavedGame = format!("givens={}\nentries={}", ".".repeat(81), entries)
+            .parse()
+            .unwrap();
+        assert!(saved.board.is_conflict(0, 0));
+        assert!(!saved.board.allow_conflicts());
+    }
+
+    #[test]
+    fn test_resume_errors() {
+        assert!("entries=".parse::<SavedGame>().is_err());
+        assert!(format!("givens={}\nlevel=1", PUZZLE)
+            .parse::<SavedGame>()
+            .is_err());
+        assert!(format!("givens={}\nelapsed=soon", PUZZLE)
+            .parse::<SavedGame>()
+            .is_err());
+    }
+}
@@ -1,13 +1,33 @@
 use rand::prelude::SliceRandom;
-use rand::thread_rng;
+use rand::rngs::StdRng;
+use rand::{thread_rng, Rng, SeedableRng};
 
-use crate::board::{Board, BOARD_SIZE};
+use crate::board::Board;
+
+pub use self::dlx::DlxSolver;
+pub use self::logical::{LogicalSolver, Step, Technique};
+pub use self::propagation::PropagationSolver;
+
+mod dlx;
+mod logical;
+mod propagation;
 
 pub trait Solver {
     fn solve(&self, board: &mut Board) -> bool;
+
+    /// Count the solutions of the board, stopping as soon as `limit` is reached.
+    fn count_solutions(&self, board: &Board, limit: usize) -> usize;
+
+    fn has_unique_solution(&self, board: &Board) -> bool {
+        self.count_solutions(board, 2) == 1
+    }
 }
 
-pub struct SimpleSolver;
+/// Fills the cells in order, trying their values in random order.
+#[derive(Default)]
+pub struct SimpleSolver {
+    seed: Option<u64>,
+}
 
 impl Solver for SimpleSolver {
     fn solve(&self, board: &mut Board) -> bool {
@@ -15,37 +35,50 @@ impl Solver for SimpleSolver {
             return true;
         }
 
-        if Self::fill_cell(&mut board.clone(), 0, 0) {
-            return Self::fill_cell(board, 0, 0);
+        let seed = self.seed.unwrap_or_else(|| thread_rng().gen());
+        if Self::fill_cell(&mut board.clone(), 0, 0, &mut StdRng::seed_from_u64(seed)) {
+            return Self::fill_cell(board, 0, 0, &mut StdRng::seed_from_u64(seed));
         }
 
         return false;
     }
+
+    fn count_solutions(&self, board: &Board, limit: usize) -> usize {
+        if limit == 0 {
+            return 0;
+        }
+        Self::count_cell(&mut board.clone(), 0, 0, limit)
+    }
 }
 
 impl SimpleSolver {
     pub fn new() -> SimpleSolver {
-        SimpleSolver
+        SimpleSolver { seed: None }
+    }
+
+    /// A solver always giving the same solution to the same board.
+    pub fn with_seed(seed: u64) -> SimpleSolver {
+        SimpleSolver { seed: Some(seed) }
     }
 
-    fn fill_cell(board: &mut Board, row: usize, col: usize) -> bool {
-        if row == BOARD_SIZE || col == BOARD_SIZE {
+    fn fill_cell(board: &mut Board, row: usize, col: usize, rng: &mut StdRng) -> bool {
+        if row == board.size() || col == board.size() {
             return true;
         }
 
         if board.get_value(row, col).is_some() {
-            let next_cell = Self::next_cell(row, col);
-            return Self::fill_cell(board, next_cell.0, next_cell.1);
+            let next_cell = Self::next_cell(board, row, col);
+            return Self::fill_cell(board, next_cell.0, next_cell.1, rng);
         }
 
         let mut available_values = board.get_available_values(row, col);
-        available_values.shuffle(&mut thread_rng());
+        available_values.shuffle(rng);
 
         for val in available_values {
             board.set_value(row, col, val).unwrap();
 
-            let next_cell = Self::next_cell(row, col);
-            if Self::fill_cell(board, next_cell.0, next_cell.1) {
+            let next_cell = Self::next_cell(board, row, col);
+            if Self::fill_cell(board, next_cell.0, next_cell.1, rng) {
                 return true;
             }
 
@@ -55,8 +88,113 @@ impl SimpleSolver {
         return false;
     }
 
-    fn next_cell(row: usize, col: usize) -> (usize, usize) {
-        let cell = row * BOARD_SIZE + col + 1;
-        (cell / BOARD_SIZE, cell % BOARD_SIZE)
+    fn count_cell(board: &mut Board, row: usize, col: usize, limit: usize) -> usize {
+        if row == board.size() || col == board.size() {
+            return 1;
+        }
+
+        let next_cell = Self::next_cell(board, row, col);
+        if board.get_value(row, col).is_some() {
+            return Self::count_cell(board, next_cell.0, next_cell.1, limit);
+        }
+
+        let mut count = 0;
+        for val in board.get_available_values(row, col) {
+            board.set_value(row, col, val).unwrap();
+            count += Self::count_cell(board, next_cell.0, next_cell.1, limit - count);
+            board.clear_value(row, col).unwrap();
+
+            if count >= limit {
+                break;
+            }
+        }
+
+        count
+    }
+
+    fn next_cell(board: &Board, row: usize, col: usize) -> (usize, usize) {
+        let cell = row * board.size() + col + 1;
+        (cell / board.size(), cell % board.size())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+
+    use crate::board::{Board, Cage, BOARD_SIZE};
+    use crate::solver::{SimpleSolver, Solver};
+
+    const PUZZLE: &str =
+        "530070000600195000098000060800060003400803001700020006060000280000419005000080079";
+    const SOLVED: &str =
+        "534678912672195348198342567859761423426853791713924856961537284287419635345286179";
+
+    /// Check that the solver solves a killer sudoku without givens, and finds no solution once a
+    /// wrong value is set.
+    pub(crate) fn assert_solves_killer(solver: &dyn Solver) {
+        // the solved grid in cages of two cells along the rows, and the last column in cages
+        // of one cell
+        let solved = SOLVED.parse::<Board>().unwrap();
+        let cages = (0..9)
+            .flat_map(|row| {
+                [(0, 2), (2, 4), (4, 6), (6, 8), (8, 9)]
+                    .iter()
+                    .map(move |r| (row, *r))
+            })
+            .map(|(row, (start, end))| {
+                let cells: Vec<(usize, usize)> = (start..end).map(|col| (row, col)).collect();
+                let sum = cells
+                    .iter()
+                    .map(|&(r, c)| solved.get_value(r, c).unwrap() as u32)
+                    .sum();
+                Cage::new(cells, sum)
+            })
+            .collect();
+        let mut board = Board::new();
+        board.set_cages(cages).unwrap();
+
+        assert!(solver.solve(&mut board));
+        assert!(board.is_solved());
+        assert_eq!(Some(2), board.get_value(0, 8));
+
+        board.reset();
+        board.set_value(0, 0, 1).unwrap();
+        assert_eq!(0, solver.count_solutions(&board, 2));
+    }
+
+    #[test]
+    fn test_count_solutions_unique() {
+        let board = PUZZLE.parse::<Board>().unwrap();
+        assert_eq!(1, SimpleSolver::new().count_solutions(&board, 10));
+        assert!(SimpleSolver::new().has_unique_solution(&board));
+    }
+
+    #[test]
+    fn test_count_solutions_limit() {
+        let board = Board::new();
+        assert_eq!(0, SimpleSolver::new().count_solutions(&board, 0));
+        assert_eq!(5, SimpleSolver::new().count_solutions(&board, 5));
+        assert!(!SimpleSolver::new().has_unique_solution(&board));
+    }
+
+    #[test]
+    fn test_solve_with_seed() {
+        let mut first = Board::new();
+        let mut second = Board::new();
+        SimpleSolver::with_seed(42).solve(&mut first);
+        SimpleSolver::with_seed(42).solve(&mut second);
+        (0..BOARD_SIZE * BOARD_SIZE).for_each(|i| {
+            let (row, col) = (i / BOARD_SIZE, i % BOARD_SIZE);
+            assert_eq!(first.get_value(row, col), second.get_value(row, col));
+        });
+    }
+
+    #[test]
+    fn test_count_solutions_none() {
+        let mut board = Board::new();
+        (0..8).for_each(|col| board.set_value(0, col, col as u8 + 1).unwrap());
+        board.set_value(1, 8, 9).unwrap();
+        assert_eq!(0, SimpleSolver::new().count_solutions(&board, 2));
+        assert!(!SimpleSolver::new().has_unique_solution(&board));
     }
 }
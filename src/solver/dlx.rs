@@ -0,0 +1,351 @@
+use crate::board::{Board, FreeNumberMask};
+use crate::solver::Solver;
+
+const ROOT: usize = 0;
+
+/// Solves the board as an exact cover problem with Knuth's Dancing Links.
+///
+/// Each candidate (row, col, val) covers a constraint for the cell being filled, and one for the
+/// value being placed in each unit of the cell: its row, its column and its box or region, and the
+/// diagonals it lies on in Sudoku X. The sums of the cages of killer sudoku don't fit in an exact
+/// cover, so the candidates breaking a cage are skipped during the search.
+#[derive(Default)]
+pub struct DlxSolver;
+
+impl Solver for DlxSolver {
+    fn solve(&self, board: &mut Board) -> bool {
+        match self.solutions(board, 1).pop() {
+            Some(solution) => {
+                *board = solution;
+                true
+            }
+            None => false,
+        }
+    }
+
+    fn count_solutions(&self, board: &Board, limit: usize) -> usize {
+        let mut count = 0;
+        if let Some(mut links) = Links::new(board) {
+            links.search(&mut vec![], limit, &mut count, &mut |_| {});
+        }
+        count
+    }
+}
+
+impl DlxSolver {
+    pub fn new() -> DlxSolver {
+        DlxSolver
+    }
+
+    /// Enumerate the solutions of the board, up to `limit`.
+    pub fn solutions(&self, board: &Board, limit: usize) -> Vec<Board> {
+        let mut solutions = vec![];
+        if let Some(mut links) = Links::new(board) {
+            links.search(&mut vec![], limit, &mut 0, &mut |candidates| {
+                let mut solution = board.clone();
+                for &(row, col, val) in candidates {
+                    solution.set_value(row, col, val).unwrap();
+                }
+                solutions.push(solution);
+            });
+        }
+        solutions
+    }
+}
+
+/// The sparse exact cover matrix, as circular doubly linked lists of nodes. Node 0 is the root,
+/// followed by one header per constraint and by the nodes of the candidates.
+struct Links {
+    left: Vec<usize>,
+    right: Vec<usize>,
+    up: Vec<usize>,
+    down: Vec<usize>,
+    header: Vec<usize>,
+    size: Vec<usize>,
+    candidate: Vec<(usize, usize, u8)>,
+    board_size: usize,
+    // the index of the cage of each cell, and for each cage its empty cells, the sum they must
+    // still add up to and the values already in it
+    cage_index: Vec<Option<usize>>,
+    cages: Vec<(usize, u32, FreeNumberMask)>,
+}
+
+impl Links {
+    /// Build the matrix of the empty cells of the board, the filled cells being already covered.
+    /// Returns `None` when the filled cells conflict with each other.
+    fn new(board: &Board) -> Option<Links> {
+        let shape = board.shape();
+        let (size, units) = (shape.size(), shape.units());
+        let nb_constraints = shape.nb_cells() + units.len() * size;
+
+        let mut links = Links {
+            left: vec![],
+            right: vec![],
+            up: vec![],
+            down: vec![],
+            header: vec![],
+            size: vec![0; nb_constraints + 1],
+            candidate: vec![],
+            board_size: size,
+            cage_index: vec![None; shape.nb_cells()],
+            cages: board
+                .cages()
+                .iter()
+                .map(|cage| (0, cage.sum(), FreeNumberMask::none()))
+                .collect(),
+        };
+        for node in 0..=nb_constraints {
+            links
+                .left
+                .push((node + nb_constraints) % (nb_constraints + 1));
+            links.right.push((node + 1) % (nb_constraints + 1));
+            links.up.push(node);
+            links.down.push(node);
+            links.header.push(node);
+            links.candidate.push((0, 0, 0));
+        }
+
+        let constraints = |row: usize, col: usize, val: u8| -> Vec<usize> {
+            let cell = row * size + col;
+            let values = units
+                .iter()
+                .enumerate()
+                .filter(|(_, unit)| shape.contains(**unit, row, col))
+                .map(|(i, _)| size * size + i * size + val as usize - 1);
+            std::iter::once(cell)
+                .chain(values)
+                .map(|constraint| constraint + 1)
+                .collect()
+        };
+
+        for (index, cage) in board.cages().iter().enumerate() {
+            for &(row, col) in cage.cells() {
+                links.cage_index[row * size + col] = Some(index);
+                links.cages[index].0 += 1;
+            }
+        }
+
+        let mut given = vec![];
+        for (row, col) in shape.positions() {
+            match board.get_value(row, col) {
+                Some(val) => {
+                    if !links.fits_cage((row, col, val)) {
+                        return None;
+                    }
+                    links.place_in_cage((row, col, val), true);
+                    given.extend(constraints(row, col, val));
+                }
+                None => {
+                    for val in board.get_available_values(row, col) {
+                        links.add_candidate((row, col, val), &constraints(row, col, val));
+                    }
+                }
+            }
+        }
+
+        given.sort_unstable();
+        if given.windows(2).any(|pair| pair[0] == pair[1]) {
+            return None;
+        }
+        for header in given {
+            links.cover(header);
+        }
+        Some(links)
+    }
+
+    fn add_candidate(&mut self, candidate: (usize, usize, u8), headers: &[usize]) {
+        let first = self.header.len();
+        for (i, &header) in headers.iter().enumerate() {
+            let node = first + i;
+            self.left
+                .push(first + (i + headers.len() - 1) % headers.len());
+            self.right.push(first + (i + 1) % headers.len());
+            self.up.push(self.up[header]);
+            self.down.push(header);
+            self.header.push(header);
+            self.candidate.push(candidate);
+
+            let last = self.up[header];
+            self.down[last] = node;
+            self.up[header] = node;
+            self.size[header] += 1;
+        }
+    }
+
+    fn search<F>(
+        &mut self,
+        selected: &mut Vec<(usize, usize, u8)>,
+        limit: usize,
+        count: &mut usize,
+        on_solution: &mut F,
+    ) where
+        F: FnMut(&[(usize, usize, u8)]),
+    {
+        if *count >= limit {
+            return;
+        }
+
+        if self.right[ROOT] == ROOT {
+            *count += 1;
+            on_solution(selected);
+            return;
+        }
+
+        let header = self.smallest_constraint();
+        if self.size[header] == 0 {
+            return;
+        }
+
+        self.cover(header);
+        let mut node = self.down[header];
+        while node != header && *count < limit {
+            let candidate = self.candidate[node];
+            if self.fits_cage(candidate) {
+                selected.push(candidate);
+                self.place_in_cage(candidate, true);
+                self.cover_row(node);
+
+                self.search(selected, limit, count, on_solution);
+
+                self.uncover_row(node);
+                self.place_in_cage(candidate, false);
+                selected.pop();
+            }
+            node = self.down[node];
+        }
+        self.uncover(header);
+    }
+
+    /// Whether the cage of the cell can still add up to its sum once the value is placed.
+    fn fits_cage(&self, (row, col, val): (usize, usize, u8)) -> bool {
+        let (nb_empty, sum, values) = match self.cage_index[row * self.board_size + col] {
+            Some(index) => self.cages[index],
+            None => return true,
+        };
+        if values.contains_value(val) || val as u32 > sum {
+            return false;
+        }
+        // the smallest and the largest sums of the cells left empty
+        let (left, max) = (nb_empty as u32 - 1, self.board_size as u32);
+        (left * (left + 1) / 2..=left * (2 * max + 1 - left) / 2).contains(&(sum - val as u32))
+    }
+
+    fn place_in_cage(&mut self, (row, col, val): (usize, usize, u8), placed: bool) {
+        if let Some(index) = self.cage_index[row * self.board_size + col] {
+            let (nb_empty, sum, values) = &mut self.cages[index];
+            if placed {
+                *nb_empty -= 1;
+                *sum -= val as u32;
+            } else {
+                *nb_empty += 1;
+                *sum += val as u32;
+            }
+            *values ^= FreeNumberMask::from_value(val);
+        }
+    }
+
+    fn smallest_constraint(&self) -> usize {
+        let mut best = self.right[ROOT];
+        let mut header = best;
+        while header != ROOT {
+            if self.size[header] < self.size[best] {
+                best = header;
+            }
+            header = self.right[header];
+        }
+        best
+    }
+
+    fn cover_row(&mut self, node: usize) {
+        let mut other = self.right[node];
+        while other != node {
+            self.cover(self.header[other]);
+            other = self.right[other];
+        }
+    }
+
+    fn uncover_row(&mut self, node: usize) {
+        let mut other = self.left[node];
+        while other != node {
+            self.uncover(self.header[other]);
+            other = self.left[other];
+        }
+    }
+
+    fn cover(&mut self, header: usize) {
+        self.right[self.left[header]] = self.right[header];
+        self.left[self.right[header]] = self.left[header];
+
+        let mut row = self.down[header];
+        while row != header {
+            let mut node = self.right[row];
+            while node != row {
+                self.down[self.up[node]] = self.down[node];
+                self.up[self.down[node]] = self.up[node];
+                self.size[self.header[node]] -= 1;
+                node = self.right[node];
+            }
+            row = self.down[row];
+        }
+    }
+
+    fn uncover(&mut self, header: usize) {
+        let mut row = self.up[header];
+        while row != header {
+            let mut node = self.left[row];
+            while node != row {
+                self.size[self.header[node]] += 1;
+                self.down[self.up[node]] = node;
+                self.up[self.down[node]] = node;
+                node = self.left[node];
+            }
+            row = self.up[row];
+        }
+
+        self.right[self.left[header]] = header;
+        self.left[self.right[header]] = header;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+
+    use crate::board::Board;
+    use crate::solver::tests::assert_solves_killer;
+    use crate::solver::{DlxSolver, Solver};
+
+    const HARDEST: &str =
+        "800000000003600000070090200050007000000045700000100030001000068008500010090000400";
+
+    #[test]
+    fn test_solve() {
+        let mut board = HARDEST.parse::<Board>().unwrap();
+        assert!(DlxSolver::new().solve(&mut board));
+        assert!(board.is_solved());
+        assert_eq!(Some(8), board.get_value(0, 0));
+    }
+
+    #[test]
+    fn test_count_solutions() {
+        assert!(DlxSolver::new().has_unique_solution(&HARDEST.parse::<Board>().unwrap()));
+        assert_eq!(0, DlxSolver::new().count_solutions(&Board::new(), 0));
+        assert_eq!(100, DlxSolver::new().count_solutions(&Board::new(), 100));
+    }
+
+    #[test]
+    fn test_solutions() {
+        let mut board = Board::new();
+        (0..8).for_each(|col| board.set_value(0, col, col as u8 + 1).unwrap());
+        board.set_value(1, 8, 9).unwrap();
+        assert!(DlxSolver::new().solutions(&board, 10).is_empty());
+
+        let solutions = DlxSolver::new().solutions(&Board::new(), 10);
+        assert_eq!(10, solutions.len());
+        assert!(solutions.iter().all(|solution| solution.is_solved()));
+    }
+
+    #[test]
+    fn test_solve_killer() {
+        assert_solves_killer(&DlxSolver::new());
+    }
+}
@@ -0,0 +1,949 @@
+use std::collections::VecDeque;
+use std::fmt::{Display, Formatter};
+
+use crate::board::{value_to_char, Board, Cage, FreeNumberMask, Shape, Unit};
+use crate::solver::{SimpleSolver, Solver};
+
+type Position = (usize, usize);
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub enum Technique {
+    HiddenSingle,
+    NakedSingle,
+    PointingPair,
+    BoxLineReduction,
+    CageCombination,
+    NakedPair,
+    XWing,
+    HiddenPair,
+    NakedTriple,
+    Swordfish,
+    HiddenTriple,
+    XYWing,
+    SimpleColoring,
+    NakedQuad,
+    HiddenQuad,
+}
+
+impl Technique {
+    /// All the techniques, from the easiest to the hardest.
+    pub const ALL: [Technique; 15] = [
+        Technique::HiddenSingle,
+        Technique::NakedSingle,
+        Technique::PointingPair,
+        Technique::BoxLineReduction,
+        Technique::CageCombination,
+        Technique::NakedPair,
+        Technique::XWing,
+        Technique::HiddenPair,
+        Technique::NakedTriple,
+        Technique::Swordfish,
+        Technique::HiddenTriple,
+        Technique::XYWing,
+        Technique::SimpleColoring,
+        Technique::NakedQuad,
+        Technique::HiddenQuad,
+    ];
+}
+
+impl From<Technique> for &'static str {
+    fn from(technique: Technique) -> &'static str {
+        match technique {
+            Technique::HiddenSingle => "Hidden single",
+            Technique::NakedSingle => "Naked single",
+            Technique::PointingPair => "Pointing pair",
+            Technique::BoxLineReduction => "Box/line reduction",
+            Technique::CageCombination => "Cage combination",
+            Technique::NakedPair => "Naked pair",
+            Technique::XWing => "X-Wing",
+            Technique::HiddenPair => "Hidden pair",
+            Technique::NakedTriple => "Naked triple",
+            Technique::Swordfish => "Swordfish",
+            Technique::HiddenTriple => "Hidden triple",
+            Technique::XYWing => "XY-Wing",
+            Technique::SimpleColoring => "Simple coloring",
+            Technique::NakedQuad => "Naked quad",
+            Technique::HiddenQuad => "Hidden quad",
+        }
+    }
+}
+
+/// One deduction of the logical solver.
+#[derive(Clone, Debug)]
+pub struct Step {
+    pub technique: Technique,
+    /// The units the pattern was found in.
+    pub units: Vec<Unit>,
+    /// The cells forming the pattern.
+    pub cells: Vec<Position>,
+    /// The values forming the pattern.
+    pub values: Vec<u8>,
+    /// The values placed by the step, as (row, col, val).
+    pub placements: Vec<(usize, usize, u8)>,
+    /// The candidates removed by the step, as (row, col, val).
+    pub eliminations: Vec<(usize, usize, u8)>,
+}
+
+impl Step {
+    fn new(technique: Technique, units: Vec<Unit>, cells: Vec<Position>, values: Vec<u8>) -> Step {
+        Step {
+            technique,
+            units,
+            cells,
+            values,
+            placements: vec![],
+            eliminations: vec![],
+        }
+    }
+
+    fn describe_eliminations(&self) -> String {
+        let mut values: Vec<u8> = self.eliminations.iter().map(|(_, _, val)| *val).collect();
+        values.sort_unstable();
+        values.dedup();
+
+        let removed: Vec<String> = values
+            .iter()
+            .map(|val| {
+                let cells: Vec<Position> = self
+                    .eliminations
+                    .iter()
+                    .filter(|(_, _, v)| v == val)
+                    .map(|(row, col, _)| (*row, *col))
+                    .collect();
+                format!("{} from {}", value_to_char(*val), join_cells(&cells))
+            })
+            .collect();
+        format!("removes {}", removed.join(" and "))
+    }
+}
+
+impl Display for Step {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}: ", Into::<&str>::into(self.technique))?;
+
+        let units = join(&self.units);
+        let cells = join_cells(&self.cells);
+        let values: Vec<char> = self.values.iter().map(|val| value_to_char(*val)).collect();
+        let values = join(&values);
+        match self.technique {
+            Technique::HiddenSingle => {
+                write!(f, "{} in {} can only go at {}", values, units, cells)
+            }
+            Technique::NakedSingle => write!(f, "{} can only be {}", cells, values),
+            Technique::PointingPair | Technique::BoxLineReduction => write!(
+                f,
+                "{} in {} is confined to {}, {}",
+                values,
+                self.units[0],
+                self.units[1],
+                self.describe_eliminations()
+            ),
+            Technique::CageCombination => write!(
+                f,
+                "cage of {} can only hold {}, {}",
+                cells,
+                values,
+                self.describe_eliminations()
+            ),
+            Technique::NakedPair | Technique::NakedTriple | Technique::NakedQuad => write!(
+                f,
+                "{} in {} can only be {}, {}",
+                cells,
+                units,
+                values,
+                self.describe_eliminations()
+            ),
+            Technique::HiddenPair | Technique::HiddenTriple | Technique::HiddenQuad => write!(
+                f,
+                "{} in {} can only go at {}, {}",
+                values,
+                units,
+                cells,
+                self.describe_eliminations()
+            ),
+            Technique::XWing | Technique::Swordfish => {
+                let (base, cover) = self.units.split_at(self.units.len() / 2);
+                write!(
+                    f,
+                    "{} in {} is confined to {}, {}",
+                    values,
+                    join(base),
+                    join(cover),
+                    self.describe_eliminations()
+                )
+            }
+            Technique::XYWing => write!(
+                f,
+                "pivot {} with wings {} and {} on {}, {}",
+                cell_name(self.cells[0]),
+                cell_name(self.cells[1]),
+                cell_name(self.cells[2]),
+                values,
+                self.describe_eliminations()
+            ),
+            Technique::SimpleColoring => write!(
+                f,
+                "chain of {} through {}, {}",
+                values,
+                cells,
+                self.describe_eliminations()
+            ),
+        }
+    }
+}
+
+/// Solves the board the way a person does, one named technique at a time.
+#[derive(Default)]
+pub struct LogicalSolver;
+
+impl Solver for LogicalSolver {
+    fn solve(&self, board: &mut Board) -> bool {
+        let mut grid = Grid::new(board);
+        while let Some(step) = grid.next_step() {
+            if grid.apply(&step).is_err() {
+                return false;
+            }
+        }
+
+        if grid.board.is_solved() {
+            *board = grid.board;
+            return true;
+        }
+        false
+    }
+
+    fn count_solutions(&self, board: &Board, limit: usize) -> usize {
+        let mut grid = Grid::new(board);
+        while let Some(step) = grid.next_step() {
+            if grid.apply(&step).is_err() {
+                return 0;
+            }
+        }
+        SimpleSolver::new().count_solutions(&grid.board, limit)
+    }
+}
+
+impl LogicalSolver {
+    pub fn new() -> LogicalSolver {
+        LogicalSolver
+    }
+
+    /// The easiest deduction that can be made on the board.
+    pub fn next_step(&self, board: &Board) -> Option<Step> {
+        Grid::new(board).next_step()
+    }
+
+    /// The deductions made to solve the board, in order. The list stops early when no technique
+    /// applies anymore.
+    pub fn steps(&self, board: &Board) -> Vec<Step> {
+        Self::steps_until(board, |_| false)
+    }
+
+    /// The deductions leading to the next value placed on the board: the eliminations needed
+    /// followed by the step placing the value. Empty if no value can be deduced.
+    pub fn steps_to_next_value(&self, board: &Board) -> Vec<Step> {
+        let steps = Self::steps_until(board, |step| !step.placements.is_empty());
+        match steps.last() {
+            Some(step) if !step.placements.is_empty() => steps,
+            _ => vec![],
+        }
+    }
+
+    fn steps_until<F>(board: &Board, stop: F) -> Vec<Step>
+    where
+        F: Fn(&Step) -> bool,
+    {
+        let mut grid = Grid::new(board);
+        let mut steps = vec![];
+        while let Some(step) = grid.next_step() {
+            if grid.apply(&step).is_err() {
+                break;
+            }
+            let done = stop(&step);
+            steps.push(step);
+            if done {
+                break;
+            }
+        }
+        steps
+    }
+}
+
+/// The board with the candidates of its empty cells. Unlike the board, the candidates keep track
+/// of the eliminations made by the previous steps.
+struct Grid {
+    board: Board,
+    candidates: Vec<Vec<FreeNumberMask>>,
+}
+
+impl Grid {
+    fn new(board: &Board) -> Grid {
+        let mut candidates = vec![vec![FreeNumberMask::none(); board.size()]; board.size()];
+        for (row, cells) in candidates.iter_mut().enumerate() {
+            for (col, cell) in cells.iter_mut().enumerate() {
+                if board.get_value(row, col).is_none() {
+                    *cell = board.get_candidates(row, col);
+                }
+            }
+        }
+
+        Grid {
+            board: board.clone(),
+            candidates,
+        }
+    }
+
+    fn apply(&mut self, step: &Step) -> Result<(), String> {
+        for &(row, col, val) in &step.placements {
+            self.board.set_value(row, col, val)?;
+            self.candidates[row][col] = FreeNumberMask::none();
+            let peers: Vec<Position> = self.peers(row, col).collect();
+            for (r, c) in peers {
+                self.candidates[r][c].unset(FreeNumberMask::from_value(val));
+            }
+        }
+
+        for &(row, col, val) in &step.eliminations {
+            self.candidates[row][col].unset(FreeNumberMask::from_value(val));
+        }
+
+        Ok(())
+    }
+
+    fn next_step(&self) -> Option<Step> {
+        Technique::ALL
+            .iter()
+            .find_map(|technique| self.find(*technique))
+    }
+
+    fn find(&self, technique: Technique) -> Option<Step> {
+        match technique {
+            Technique::HiddenSingle => self.hidden_single(),
+            Technique::NakedSingle => self.naked_single(),
+            Technique::PointingPair => self.pointing_pair(),
+            Technique::BoxLineReduction => self.box_line_reduction(),
+            Technique::CageCombination => self.cage_combination(),
+            Technique::NakedPair => self.naked_subset(technique, 2),
+            Technique::NakedTriple => self.naked_subset(technique, 3),
+            Technique::NakedQuad => self.naked_subset(technique, 4),
+            Technique::HiddenPair => self.hidden_subset(technique, 2),
+            Technique::HiddenTriple => self.hidden_subset(technique, 3),
+            Technique::HiddenQuad => self.hidden_subset(technique, 4),
+            Technique::XWing => self.fish(technique, 2),
+            Technique::Swordfish => self.fish(technique, 3),
+            Technique::XYWing => self.xy_wing(),
+            Technique::SimpleColoring => self.simple_coloring(),
+        }
+    }
+
+    fn hidden_single(&self) -> Option<Step> {
+        for unit in self.shape().units() {
+            for val in 1..=self.size() as u8 {
+                if let [(row, col)] = self.positions(&unit, val)[..] {
+                    return Some(Step {
+                        placements: vec![(row, col, val)],
+                        ..Step::new(
+                            Technique::HiddenSingle,
+                            vec![unit],
+                            vec![(row, col)],
+                            vec![val],
+                        )
+                    });
+                }
+            }
+        }
+        None
+    }
+
+    fn naked_single(&self) -> Option<Step> {
+        for (row, col) in self.cells() {
+            if let [val] = self.candidates[row][col].values()[..] {
+                return Some(Step {
+                    placements: vec![(row, col, val)],
+                    ..Step::new(Technique::NakedSingle, vec![], vec![(row, col)], vec![val])
+                });
+            }
+        }
+        None
+    }
+
+    /// A value confined to one row or column inside a box is removed from the rest of the line.
+    fn pointing_pair(&self) -> Option<Step> {
+        for b in 0..self.size() {
+            let unit = Unit::Box(b);
+            for val in 1..=self.size() as u8 {
+                let positions = self.positions(&unit, val);
+                if positions.len() < 2 {
+                    continue;
+                }
+
+                let (row, col) = positions[0];
+                for line in [Unit::Row(row), Unit::Column(col)].iter() {
+                    if !positions
+                        .iter()
+                        .all(|(r, c)| self.shape().contains(*line, *r, *c))
+                    {
+                        continue;
+                    }
+
+                    let others = self
+                        .shape()
+                        .cells(*line)
+                        .into_iter()
+                        .filter(|(r, c)| !self.shape().contains(unit, *r, *c));
+                    let eliminations = self.eliminations(others, &[val]);
+                    if !eliminations.is_empty() {
+                        return Some(Step {
+                            eliminations,
+                            ..Step::new(
+                                Technique::PointingPair,
+                                vec![unit, *line],
+                                positions,
+                                vec![val],
+                            )
+                        });
+                    }
+                }
+            }
+        }
+        None
+    }
+
+    /// A value confined to one box inside a row or column is removed from the rest of the box.
+    fn box_line_reduction(&self) -> Option<Step> {
+        let lines = (0..self.size())
+            .map(Unit::Row)
+            .chain((0..self.size()).map(Unit::Column));
+        for line in lines {
+            for val in 1..=self.size() as u8 {
+                let positions = self.positions(&line, val);
+                if positions.len() < 2 {
+                    continue;
+                }
+
+                let (row, col) = positions[0];
+                let unit = Unit::Box(self.shape().box_index(row, col));
+                if !positions
+                    .iter()
+                    .all(|(r, c)| self.shape().contains(unit, *r, *c))
+                {
+                    continue;
+                }
+
+                let others = self
+                    .shape()
+                    .cells(unit)
+                    .into_iter()
+                    .filter(|(r, c)| !self.shape().contains(line, *r, *c));
+                let eliminations = self.eliminations(others, &[val]);
+                if !eliminations.is_empty() {
+                    return Some(Step {
+                        eliminations,
+                        ..Step::new(
+                            Technique::BoxLineReduction,
+                            vec![line, unit],
+                            positions,
+                            vec![val],
+                        )
+                    });
+                }
+            }
+        }
+        None
+    }
+
+    /// N cells of a unit sharing only N candidates: the candidates are removed from the rest of
+    /// the unit.
+    fn naked_subset(&self, technique: Technique, size: usize) -> Option<Step> {
+        for unit in self.shape().units() {
+            let cells: Vec<Position> = self
+                .shape()
+                .cells(unit)
+                .into_iter()
+                .filter(|(r, c)| {
+                    (2..=size).contains(&(self.candidates[*r][*c].count_ones() as usize))
+                })
+                .collect();
+
+            for subset in combinations(&cells, size) {
+                let union = subset.iter().fold(FreeNumberMask::none(), |mask, (r, c)| {
+                    mask | self.candidates[*r][*c]
+                });
+                if union.count_ones() as usize != size {
+                    continue;
+                }
+
+                let values = union.values();
+                let others = self
+                    .shape()
+                    .cells(unit)
+                    .into_iter()
+                    .filter(|cell| !subset.contains(cell));
+                let eliminations = self.eliminations(others, &values);
+                if !eliminations.is_empty() {
+                    return Some(Step {
+                        eliminations,
+                        ..Step::new(technique, vec![unit], subset, values)
+                    });
+                }
+            }
+        }
+        None
+    }
+
+    /// N values of a unit confined to the same N cells: the other candidates are removed from
+    /// these cells.
+    fn hidden_subset(&self, technique: Technique, size: usize) -> Option<Step> {
+        for unit in self.shape().units() {
+            let values: Vec<u8> = (1..=self.size() as u8)
+                .filter(|val| (2..=size).contains(&self.positions(&unit, *val).len()))
+                .collect();
+
+            for subset in combinations(&values, size) {
+                let cells: Vec<Position> = self
+                    .shape()
+                    .cells(unit)
+                    .into_iter()
+                    .filter(|(r, c)| {
+                        subset
+                            .iter()
+                            .any(|val| self.candidates[*r][*c].contains_value(*val))
+                    })
+                    .collect();
+                if cells.len() != size {
+                    continue;
+                }
+
+                let others: Vec<u8> = (1..=self.size() as u8)
+                    .filter(|val| !subset.contains(val))
+                    .collect();
+                let eliminations = self.eliminations(cells.iter().copied(), &others);
+                if !eliminations.is_empty() {
+                    return Some(Step {
+                        eliminations,
+                        ..Step::new(technique, vec![unit], cells, subset)
+                    });
+                }
+            }
+        }
+        None
+    }
+
+    /// A value confined to the same N columns in N rows (or the reverse) is removed from the rest
+    /// of these columns.
+    fn fish(&self, technique: Technique, size: usize) -> Option<Step> {
+        for val in 1..=self.size() as u8 {
+            for by_rows in [true, false].iter() {
+                let base = |i| {
+                    if *by_rows {
+                        Unit::Row(i)
+                    } else {
+                        Unit::Column(i)
+                    }
+                };
+                let cover = |i| {
+                    if *by_rows {
+                        Unit::Column(i)
+                    } else {
+                        Unit::Row(i)
+                    }
+                };
+
+                let lines: Vec<(usize, u32)> = (0..self.size())
+                    .map(|i| {
+                        let mask = self
+                            .shape()
+                            .cells(base(i))
+                            .iter()
+                            .enumerate()
+                            .filter(|(_, (r, c))| self.candidates[*r][*c].contains_value(val))
+                            .fold(0u32, |mask, (j, _)| mask | 1 << j);
+                        (i, mask)
+                    })
+                    .filter(|(_, mask)| (2..=size).contains(&(mask.count_ones() as usize)))
+                    .collect();
+
+                for subset in combinations(&lines, size) {
+                    let union = subset.iter().fold(0u32, |union, (_, mask)| union | mask);
+                    if union.count_ones() as usize != size {
+                        continue;
+                    }
+
+                    let base_units: Vec<Unit> = subset.iter().map(|(i, _)| base(*i)).collect();
+                    let cover_units: Vec<Unit> = (0..self.size())
+                        .filter(|j| union & 1 << j != 0)
+                        .map(cover)
+                        .collect();
+
+                    let others = cover_units
+                        .iter()
+                        .flat_map(|unit| self.shape().cells(*unit))
+                        .filter(|(r, c)| {
+                            !base_units
+                                .iter()
+                                .any(|unit| self.shape().contains(*unit, *r, *c))
+                        });
+                    let eliminations = self.eliminations(others, &[val]);
+                    if !eliminations.is_empty() {
+                        let cells = base_units
+                            .iter()
+                            .flat_map(|unit| self.shape().cells(*unit))
+                            .filter(|(r, c)| self.candidates[*r][*c].contains_value(val))
+                            .collect();
+                        let units = base_units.into_iter().chain(cover_units).collect();
+                        return Some(Step {
+                            eliminations,
+                            ..Step::new(technique, units, cells, vec![val])
+                        });
+                    }
+                }
+            }
+        }
+        None
+    }
+
+    /// A cell {X,Y} seeing two cells {X,Z} and {Y,Z}: one of them is Z, so Z is removed from the
+    /// cells seeing both.
+    fn xy_wing(&self) -> Option<Step> {
+        let bivalues: Vec<Position> = self
+            .cells()
+            .filter(|(r, c)| self.candidates[*r][*c].count_ones() == 2)
+            .collect();
+
+        for &pivot in &bivalues {
+            let pivot_mask = self.candidates[pivot.0][pivot.1];
+            for &first in bivalues.iter().filter(|cell| self.sees(pivot, **cell)) {
+                let first_mask = self.candidates[first.0][first.1];
+                let common = first_mask & pivot_mask;
+                if common.count_ones() != 1 {
+                    continue;
+                }
+
+                let z = first_mask & !pivot_mask;
+                let second_mask = (pivot_mask & !common) | z;
+                for &second in bivalues.iter().filter(|cell| self.sees(pivot, **cell)) {
+                    if second == first || self.candidates[second.0][second.1] != second_mask {
+                        continue;
+                    }
+
+                    let others = self.cells().filter(|cell| {
+                        *cell != pivot && self.sees(first, *cell) && self.sees(second, *cell)
+                    });
+                    let eliminations = self.eliminations(others, &z.values());
+                    if !eliminations.is_empty() {
+                        return Some(Step {
+                            eliminations,
+                            ..Step::new(
+                                Technique::XYWing,
+                                vec![],
+                                vec![pivot, first, second],
+                                (pivot_mask | z).values(),
+                            )
+                        });
+                    }
+                }
+            }
+        }
+        None
+    }
+
+    /// Chains of conjugate pairs (units with exactly two positions for a value) are colored with
+    /// two alternating colors, one of which is true. A color appearing twice in a unit is false,
+    /// and a cell seeing both colors can't hold the value.
+    fn simple_coloring(&self) -> Option<Step> {
+        for val in 1..=self.size() as u8 {
+            let links: Vec<(Position, Position)> = self
+                .shape()
+                .units()
+                .iter()
+                .filter_map(|unit| match self.positions(unit, val)[..] {
+                    [first, second] => Some((first, second)),
+                    _ => None,
+                })
+                .collect();
+
+            let mut colored: Vec<Position> = vec![];
+            for &(start, _) in &links {
+                if colored.contains(&start) {
+                    continue;
+                }
+
+                let chain = Self::color_chain(&links, start);
+                colored.extend(chain[0].iter().chain(chain[1].iter()));
+                let cells: Vec<Position> =
+                    chain[0].iter().chain(chain[1].iter()).copied().collect();
+
+                for color in chain.iter() {
+                    let wrap = color
+                        .iter()
+                        .any(|a| color.iter().any(|b| self.sees(*a, *b)));
+                    if wrap {
+                        let eliminations = color.iter().map(|(r, c)| (*r, *c, val)).collect();
+                        return Some(Step {
+                            eliminations,
+                            ..Step::new(Technique::SimpleColoring, vec![], cells, vec![val])
+                        });
+                    }
+                }
+
+                let others = self.cells().filter(|cell| {
+                    !cells.contains(cell)
+                        && chain[0].iter().any(|a| self.sees(*a, *cell))
+                        && chain[1].iter().any(|b| self.sees(*b, *cell))
+                });
+                let eliminations = self.eliminations(others, &[val]);
+                if !eliminations.is_empty() {
+                    return Some(Step {
+                        eliminations,
+                        ..Step::new(Technique::SimpleColoring, vec![], cells, vec![val])
+                    });
+                }
+            }
+        }
+        None
+    }
+
+    /// The cells linked to `start`, split by color.
+    fn color_chain(links: &[(Position, Position)], start: Position) -> [Vec<Position>; 2] {
+        let mut chain = [vec![start], vec![]];
+        let mut queue = VecDeque::from(vec![(start, 0)]);
+
+        while let Some((cell, color)) = queue.pop_front() {
+            let linked = links.iter().filter_map(|&(a, b)| {
+                if a == cell {
+                    Some(b)
+                } else if b == cell {
+                    Some(a)
+                } else {
+                    None
+                }
+            });
+            for next in linked {
+                if !chain[0].contains(&next) && !chain[1].contains(&next) {
+                    chain[1 - color].push(next);
+                    queue.push_back((next, 1 - color));
+                }
+            }
+        }
+
+        chain
+    }
+
+    /// The cells of a cage keep only the values of the combinations adding up to its sum.
+    fn cage_combination(&self) -> Option<Step> {
+        for cage in self.board.cages() {
+            let candidates: Vec<FreeNumberMask> = cage
+                .cells()
+                .iter()
+                .map(|&(r, c)| match self.board.get_value(r, c) {
+                    Some(val) => FreeNumberMask::from_value(val),
+                    None => self.candidates[r][c],
+                })
+                .collect();
+            let allowed = Cage::restrict(&cage.combinations(self.size()), &candidates);
+
+            let eliminations: Vec<(usize, usize, u8)> = cage
+                .cells()
+                .iter()
+                .zip(candidates.into_iter().zip(allowed.iter()))
+                .filter(|((r, c), _)| self.board.get_value(*r, *c).is_none())
+                .flat_map(|(&(r, c), (before, after))| {
+                    (before ^ *after)
+                        .values()
+                        .into_iter()
+                        .map(move |val| (r, c, val))
+                })
+                .collect();
+            if !eliminations.is_empty() {
+                let values = allowed
+                    .iter()
+                    .fold(FreeNumberMask::none(), |values, cell| values | *cell);
+                return Some(Step {
+                    eliminations,
+                    ..Step::new(
+                        Technique::CageCombination,
+                        vec![],
+                        cage.cells().to_vec(),
+                        values.values(),
+                    )
+                });
+            }
+        }
+        None
+    }
+
+    fn positions(&self, unit: &Unit, val: u8) -> Vec<Position> {
+        self.shape()
+            .cells(*unit)
+            .into_iter()
+            .filter(|(r, c)| self.candidates[*r][*c].contains_value(val))
+            .collect()
+    }
+
+    /// The candidates among `values` still present in `cells`.
+    fn eliminations<I>(&self, cells: I, values: &[u8]) -> Vec<(usize, usize, u8)>
+    where
+        I: Iterator<Item = Position>,
+    {
+        cells
+            .flat_map(|(r, c)| {
+                values
+                    .iter()
+                    .filter(move |val| self.candidates[r][c].contains_value(**val))
+                    .map(move |val| (r, c, *val))
+            })
+            .collect()
+    }
+
+    fn shape(&self) -> &Shape {
+        self.board.shape()
+    }
+
+    fn size(&self) -> usize {
+        self.board.size()
+    }
+
+    fn cells(&self) -> impl Iterator<Item = Position> {
+        self.shape().positions()
+    }
+
+    fn peers(&self, row: usize, col: usize) -> impl Iterator<Item = Position> + '_ {
+        self.cells()
+            .filter(move |cell| self.sees((row, col), *cell))
+    }
+
+    /// Whether two different cells can't hold the same value, sharing a unit or a cage.
+    fn sees(&self, a: Position, b: Position) -> bool {
+        self.shape().sees(a, b)
+            || (a != b
+                && self
+                    .board
+                    .get_cage(a.0, a.1)
+                    .is_some_and(|cage| cage.contains(b.0, b.1)))
+    }
+}
+
+fn cell_name((row, col): Position) -> String {
+    format!("r{}c{}", row + 1, col + 1)
+}
+
+fn join_cells(cells: &[Position]) -> String {
+    let names: Vec<String> = cells.iter().map(|cell| cell_name(*cell)).collect();
+    names.join(", ")
+}
+
+fn join<T: Display>(items: &[T]) -> String {
+    let names: Vec<String> = items.iter().map(|item| item.to_string()).collect();
+    names.join(", ")
+}
+
+fn combinations<T: Copy>(items: &[T], size: usize) -> Vec<Vec<T>> {
+    if size == 0 {
+        return vec![vec![]];
+    }
+    if items.len() < size {
+        return vec![];
+    }
+
+    let mut result: Vec<Vec<T>> = combinations(&items[1..], size - 1)
+        .into_iter()
+        .map(|mut subset| {
+            subset.insert(0, items[0]);
+            subset
+        })
+        .collect();
+    result.extend(combinations(&items[1..], size));
+    result
+}
+
+#[cfg(test)]
+mod tests {
+
+    use crate::board::{Board, Cage, FreeNumberMask};
+    use crate::solver::logical::{combinations, Grid};
+    use crate::solver::{LogicalSolver, SimpleSolver, Solver, Technique};
+
+    const X_WING: &str =
+        "100000569492056108056109240009640801064010000218035604040500016905061402621000005";
+    const XY_WING: &str =
+        "900040000000600031020000090000700020002935600070002000060000073510009000000080009";
+    const TOO_HARD: &str =
+        "800000000003600000070090200050007000000045700000100030001000068008500010090000400";
+
+    fn assert_uses(puzzle: &str, technique: Technique) {
+        let board: Board = puzzle.parse().unwrap();
+        let mut solution = board.clone();
+        assert!(SimpleSolver::new().solve(&mut solution));
+
+        let steps = LogicalSolver::new().steps(&board);
+        assert!(steps.iter().any(|step| step.technique == technique));
+        for step in steps {
+            for (row, col, val) in step.placements {
+                assert_eq!(Some(val), solution.get_value(row, col));
+            }
+            for (row, col, val) in step.eliminations {
+                assert_ne!(Some(val), solution.get_value(row, col));
+            }
+        }
+    }
+
+    #[test]
+    fn test_x_wing() {
+        assert_uses(X_WING, Technique::XWing);
+    }
+
+    #[test]
+    fn test_xy_wing() {
+        assert_uses(XY_WING, Technique::XYWing);
+    }
+
+    #[test]
+    fn test_solve() {
+        let mut board = X_WING.parse::<Board>().unwrap();
+        assert!(LogicalSolver::new().solve(&mut board));
+        assert!(board.is_solved());
+
+        let mut board = TOO_HARD.parse::<Board>().unwrap();
+        assert!(!LogicalSolver::new().solve(&mut board));
+        assert!(board.get_value(0, 1).is_none());
+        assert_eq!(1, LogicalSolver::new().count_solutions(&board, 2));
+    }
+
+    #[test]
+    fn test_describe() {
+        let step = LogicalSolver::new()
+            .next_step(&X_WING.parse::<Board>().unwrap())
+            .unwrap();
+        assert_eq!(
+            "Pointing pair: 9 in box 5 is confined to column 4, removes 9 from r9c4",
+            step.to_string()
+        );
+    }
+
+    #[test]
+    fn test_cage_combination() {
+        let mut board = Board::new();
+        board
+            .set_cages(vec![Cage::new(vec![(0, 0), (1, 0)], 10)])
+            .unwrap();
+        let mut grid = Grid::new(&board);
+        assert!(grid.find(Technique::CageCombination).is_none());
+
+        grid.candidates[0][0] = FreeNumberMask::from_value(1) | FreeNumberMask::from_value(2);
+        let step = grid.find(Technique::CageCombination).unwrap();
+        assert_eq!(vec![1, 2, 8, 9], step.values);
+        assert_eq!(6, step.eliminations.len());
+        assert!(step.to_string().starts_with(
+            "Cage combination: cage of r1c1, r2c1 can only hold 1, 2, 8, 9, removes 1 from r2c1"
+        ));
+    }
+
+    #[test]
+    fn test_combinations() {
+        assert_eq!(
+            vec![vec![1, 2], vec![1, 3], vec![2, 3]],
+            combinations(&[1, 2, 3], 2)
+        );
+        assert!(combinations(&[1, 2], 3).is_empty());
+    }
+}
@@ -0,0 +1,444 @@
+use rand::prelude::SliceRandom;
+use rand::rngs::StdRng;
+use rand::SeedableRng;
+
+use crate::board::{Board, Cage, FreeNumberMask, MAX_BOARD_SIZE};
+use crate::solver::Solver;
+
+/// Solves the board by propagating the constraints after each placement, and by branching on the
+/// cell with the fewest candidates when propagation is not enough.
+///
+/// A value placed in a cell is removed from the candidates of its peers, a cell left with one
+/// candidate is filled, and a value left with one position in a unit is placed there. In killer
+/// sudoku, the cells of a cage keep only the values of the combinations adding up to its sum.
+#[derive(Default)]
+pub struct PropagationSolver {
+    seed: Option<u64>,
+}
+
+impl Solver for PropagationSolver {
+    fn solve(&self, board: &mut Board) -> bool {
+        self.try_solve(board, usize::MAX).unwrap()
+    }
+
+    fn count_solutions(&self, board: &Board, limit: usize) -> usize {
+        self.try_count_solutions(board, limit, usize::MAX).unwrap()
+    }
+}
+
+impl PropagationSolver {
+    pub fn new() -> PropagationSolver {
+        PropagationSolver { seed: None }
+    }
+
+    /// A solver trying the values in an order given by the seed, so that solving an empty board
+    /// gives a random grid, always the same for the same seed.
+    pub fn with_seed(seed: u64) -> PropagationSolver {
+        PropagationSolver { seed: Some(seed) }
+    }
+
+    /// Solve the board like `solve`, but give up and return `None` after branching
+    /// `max_branches` times.
+    pub fn try_solve(&self, board: &mut Board, max_branches: usize) -> Option<bool> {
+        let constraints = Constraints::new(board);
+        let mut rng = self.seed.map(StdRng::seed_from_u64);
+        let (mut solution, mut branches) = (None, max_branches);
+        if let Some(state) = State::new(board, &constraints) {
+            state.search(
+                &constraints,
+                1,
+                &mut 0,
+                &mut branches,
+                &mut rng,
+                &mut |state| solution = Some(*state),
+            );
+        }
+
+        match solution {
+            Some(state) => {
+                for (cell, (row, col)) in board.shape().positions().enumerate() {
+                    if board.get_value(row, col).is_none() {
+                        board.set_value(row, col, state.value(cell)).unwrap();
+                    }
+                }
+                Some(true)
+            }
+            None if branches == 0 => None,
+            None => Some(false),
+        }
+    }
+
+    /// Count the solutions of the board like `count_solutions`, but give up and return `None`
+    /// after branching `max_branches` times.
+    pub fn try_count_solutions(
+        &self,
+        board: &Board,
+        limit: usize,
+        max_branches: usize,
+    ) -> Option<usize> {
+        let constraints = Constraints::new(board);
+        let (mut count, mut branches) = (0, max_branches);
+        if let Some(state) = State::new(board, &constraints) {
+            state.search(
+                &constraints,
+                limit,
+                &mut count,
+                &mut branches,
+                &mut None,
+                &mut |_| {},
+            );
+        }
+        if branches == 0 && count < limit {
+            return None;
+        }
+        Some(count)
+    }
+}
+
+/// The cells of each unit, the units of each cell and the peers of each cell, with the cells and
+/// the combinations of each cage and the cage of each cell.
+struct Constraints {
+    size: usize,
+    nb_cells: usize,
+    units: Vec<Vec<usize>>,
+    cell_units: Vec<Vec<usize>>,
+    peers: Vec<Vec<usize>>,
+    cage_cells: Vec<Vec<usize>>,
+    cage_combinations: Vec<Vec<FreeNumberMask>>,
+    cell_cages: Vec<Vec<usize>>,
+}
+
+impl Constraints {
+    fn new(board: &Board) -> Constraints {
+        let shape = board.shape();
+        let (size, nb_cells) = (shape.size(), shape.nb_cells());
+        let units: Vec<Vec<usize>> = shape
+            .units()
+            .into_iter()
+            .map(|unit| {
+                shape
+                    .cells(unit)
+                    .iter()
+                    .map(|(row, col)| row * size + col)
+                    .collect()
+            })
+            .collect();
+
+        let cell_units: Vec<Vec<usize>> = (0..nb_cells)
+            .map(|cell| {
+                (0..units.len())
+                    .filter(|u| units[*u].contains(&cell))
+                    .collect()
+            })
+            .collect();
+
+        let peers = (0..nb_cells)
+            .map(|cell| {
+                let mut peers: Vec<usize> = cell_units[cell]
+                    .iter()
+                    .flat_map(|u| units[*u].iter().copied())
+                    .filter(|peer| *peer != cell)
+                    .collect();
+                peers.sort_unstable();
+                peers.dedup();
+                peers
+            })
+            .collect();
+
+        let mut cages: Vec<Cage> = board.cages().to_vec();
+        cages.extend(Self::unit_cages(board));
+        let mut cell_cages = vec![vec![]; nb_cells];
+        for (index, cage) in cages.iter().enumerate() {
+            for (row, col) in cage.cells() {
+                cell_cages[row * size + col].push(index);
+            }
+        }
+
+        Constraints {
+            size,
+            nb_cells,
+            units,
+            cell_units,
+            peers,
+            cage_cells: cages
+                .iter()
+                .map(|cage| {
+                    cage.cells()
+                        .iter()
+                        .map(|(row, col)| row * size + col)
+                        .collect()
+                })
+                .collect(),
+            cage_combinations: cages.iter().map(|cage| cage.combinations(size)).collect(),
+            cell_cages,
+        }
+    }
+
+    /// The cages hidden in the units of a killer sudoku: the values of a unit adding up to the
+    /// sum of all values, its cells outside the cages lying inside the unit add up to what the
+    /// sums of these cages leave.
+    fn unit_cages(board: &Board) -> Vec<Cage> {
+        let shape = board.shape();
+        let total = (1..=shape.size() as u32).sum::<u32>();
+        shape
+            .units()
+            .into_iter()
+            .filter_map(|unit| {
+                let cells = shape.cells(unit);
+                let cages: Vec<&Cage> = cells
+                    .iter()
+                    .map(|(row, col)| board.get_cage(*row, *col))
+                    .collect::<Option<_>>()?;
+                let inside: Vec<&Cage> = board
+                    .cages()
+                    .iter()
+                    .filter(|cage| cages.contains(cage))
+                    .filter(|cage| cage.cells().iter().all(|cell| cells.contains(cell)))
+                    .collect();
+                let rest: Vec<(usize, usize)> = cells
+                    .into_iter()
+                    .filter(|(row, col)| !inside.iter().any(|cage| cage.contains(*row, *col)))
+                    .collect();
+                let sum = total - inside.iter().map(|cage| cage.sum()).sum::<u32>();
+                match inside.is_empty() || rest.is_empty() || rest.len() > shape.size() / 2 {
+                    true => None,
+                    false => Some(Cage::new(rest, sum)),
+                }
+            })
+            .collect()
+    }
+}
+
+/// The candidates of each cell, in an array large enough for every board so that branching only
+/// copies the state instead of allocating one. The cells past the board are unused.
+#[derive(Clone, Copy)]
+struct State {
+    candidates: [FreeNumberMask; MAX_BOARD_SIZE * MAX_BOARD_SIZE],
+}
+
+impl State {
+    /// The state with the filled cells of the board assigned, or `None` if they contradict.
+    fn new(board: &Board, constraints: &Constraints) -> Option<State> {
+        let mut state = State {
+            candidates: [FreeNumberMask::up_to(board.size()); MAX_BOARD_SIZE * MAX_BOARD_SIZE],
+        };
+        for (cell, (row, col)) in board.shape().positions().enumerate() {
+            if let Some(val) = board.get_value(row, col) {
+                if !state.assign(constraints, cell, val) {
+                    return None;
+                }
+            }
+        }
+        if !(0..constraints.cage_cells.len()).all(|cage| state.restrict_cage(constraints, cage)) {
+            return None;
+        }
+        Some(state)
+    }
+
+    fn search<F>(
+        &self,
+        constraints: &Constraints,
+        limit: usize,
+        count: &mut usize,
+        branches: &mut usize,
+        rng: &mut Option<StdRng>,
+        on_solution: &mut F,
+    ) where
+        F: FnMut(&State),
+    {
+        if *count >= limit {
+            return;
+        }
+
+        let cell = (0..constraints.nb_cells)
+            .filter(|cell| self.candidates[*cell].count_ones() > 1)
+            .min_by_key(|cell| self.candidates[*cell].count_ones());
+
+        let cell = match cell {
+            Some(cell) => cell,
+            None => {
+                *count += 1;
+                on_solution(self);
+                return;
+            }
+        };
+
+        if *branches == 0 {
+            return;
+        }
+        *branches -= 1;
+
+        let mut values = [0; MAX_BOARD_SIZE];
+        let mut nb_values = 0;
+        for val in
+            (1..=constraints.size as u8).filter(|val| self.candidates[cell].contains_value(*val))
+        {
+            values[nb_values] = val;
+            nb_values += 1;
+        }
+        let values = &mut values[..nb_values];
+        if let Some(rng) = rng {
+            values.shuffle(rng);
+        }
+        for val in values.iter() {
+            if *count >= limit {
+                break;
+            }
+
+            let mut next = *self;
+            if next.assign(constraints, cell, *val) {
+                next.search(constraints, limit, count, branches, rng, on_solution);
+            }
+        }
+    }
+
+    fn value(&self, cell: usize) -> u8 {
+        self.candidates[cell].trailing_zeros() as u8 + 1
+    }
+
+    /// Remove every other candidate of the cell. Returns false on contradiction.
+    fn assign(&mut self, constraints: &Constraints, cell: usize, val: u8) -> bool {
+        (1..=constraints.size as u8)
+            .filter(|other| *other != val)
+            .all(|other| self.eliminate(constraints, cell, other))
+    }
+
+    /// Remove a candidate of the cell and propagate. Returns false on contradiction.
+    fn eliminate(&mut self, constraints: &Constraints, cell: usize, val: u8) -> bool {
+        let flag = FreeNumberMask::from_value(val);
+        if !self.candidates[cell].contains(flag) {
+            return true;
+        }
+        self.candidates[cell].unset(flag);
+
+        match self.candidates[cell].count_ones() {
+            0 => return false,
+            1 => {
+                let single = self.value(cell);
+                for peer in constraints.peers[cell].iter() {
+                    if !self.eliminate(constraints, *peer, single) {
+                        return false;
+                    }
+                }
+            }
+            _ => {}
+        }
+
+        for unit in constraints.cell_units[cell].iter() {
+            let mut places = constraints.units[*unit]
+                .iter()
+                .filter(|other| self.candidates[**other].contains(flag));
+
+            match (places.next(), places.next()) {
+                (None, _) => return false,
+                (Some(place), None)
+                    if self.candidates[*place].count_ones() > 1
+                        && !self.assign(constraints, *place, val) =>
+                {
+                    return false
+                }
+                _ => {}
+            }
+        }
+
+        constraints.cell_cages[cell]
+            .iter()
+            .all(|cage| self.restrict_cage(constraints, *cage))
+    }
+
+    /// Remove the candidates of the cells of the cage fitting none of its combinations. Returns
+    /// false on contradiction.
+    fn restrict_cage(&mut self, constraints: &Constraints, cage: usize) -> bool {
+        // a cage has at most one cell per value
+        let cells = &constraints.cage_cells[cage];
+        let mut candidates = [FreeNumberMask::none(); MAX_BOARD_SIZE];
+        let mut allowed = [FreeNumberMask::none(); MAX_BOARD_SIZE];
+        for (i, cell) in cells.iter().enumerate() {
+            candidates[i] = self.candidates[*cell];
+        }
+        Cage::restrict_into(
+            &constraints.cage_combinations[cage],
+            &candidates[..cells.len()],
+            &mut allowed[..cells.len()],
+        );
+        cells
+            .iter()
+            .zip(candidates.iter().zip(allowed.iter()))
+            .all(|(cell, (before, after))| {
+                let removed = *before ^ *after;
+                (1..=constraints.size as u8)
+                    .filter(|val| removed.contains_value(*val))
+                    .all(|val| self.eliminate(constraints, *cell, val))
+            })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+
+    use std::time::{Duration, Instant};
+
+    use crate::board::Board;
+    use crate::solver::tests::assert_solves_killer;
+    use crate::solver::{PropagationSolver, SimpleSolver, Solver};
+
+    const HARDEST: &str =
+        "800000000003600000070090200050007000000045700000100030001000068008500010090000400";
+
+    #[test]
+    fn test_solve() {
+        let mut board = HARDEST.parse::<Board>().unwrap();
+        assert!(PropagationSolver::new().solve(&mut board));
+        assert!(board.is_solved());
+        assert_eq!(Some(8), board.get_value(0, 0));
+    }
+
+    #[test]
+    fn test_count_solutions() {
+        assert!(PropagationSolver::new().has_unique_solution(&HARDEST.parse::<Board>().unwrap()));
+        assert_eq!(
+            0,
+            PropagationSolver::new().count_solutions(&Board::new(), 0)
+        );
+        assert_eq!(
+            100,
+            PropagationSolver::new().count_solutions(&Board::new(), 100)
+        );
+
+        let mut board = Board::new();
+        (0..8).for_each(|col| board.set_value(0, col, col as u8 + 1).unwrap());
+        board.set_value(1, 8, 9).unwrap();
+        assert_eq!(0, PropagationSolver::new().count_solutions(&board, 2));
+    }
+
+    #[test]
+    fn test_solve_killer() {
+        assert_solves_killer(&PropagationSolver::new());
+    }
+
+    #[test]
+    #[ignore = "timed, run alone with cargo test --release -- --ignored"]
+    fn test_faster_than_simple() {
+        let puzzles = [
+            HARDEST,
+            "6.....8.3.4.7.................5.4.7.3..2.....1.6.......2.....5.....8.6......1....",
+        ];
+        let time = |solver: &dyn Solver| {
+            let start = Instant::now();
+            for puzzle in puzzles.iter() {
+                assert_eq!(1, solver.count_solutions(&puzzle.parse().unwrap(), 2));
+            }
+            start.elapsed()
+        };
+
+        let propagation = time(&PropagationSolver::new());
+        let simple = time(&SimpleSolver::new());
+        assert!(
+            propagation * 10 < simple,
+            "{:?} against {:?} for SimpleSolver",
+            propagation,
+            simple
+        );
+        assert!(propagation < Duration::from_secs(1));
+    }
+}